@@ -0,0 +1,337 @@
+//!
+//! Configuration file support. The [Config] is read from
+//! `$XDG_CONFIG_HOME/niri-launcher/config.toml` (or
+//! `~/.config/niri-launcher/config.toml`) and provides defaults for values
+//! which are not passed with command line arguments.
+//!
+
+use crate::{
+    editor::EditorFrontend,
+    env_diff::EnvDiff,
+    provider::{KittyContext, NvimContext},
+    EnvPolicy, Error, KittyInto, Result,
+};
+use serde::Deserialize;
+use std::{collections::HashMap, env, fs, path::PathBuf};
+
+/// Name of directory inside `$XDG_CONFIG_HOME` with configuration
+const CONFIG_DIR: &str = "niri-launcher";
+
+/// Name of configuration file inside [CONFIG_DIR]
+const CONFIG_FILE: &str = "config.toml";
+
+/// Top-level configuration
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Path to niri socket
+    pub niri_socket: Option<PathBuf>,
+
+    /// Kitty related settings
+    pub kitty: KittyConfig,
+
+    /// Neovide related settings
+    pub neovide: NeovideConfig,
+
+    /// Settings of `vim` command
+    pub editor: EditorConfig,
+
+    /// WezTerm related settings
+    pub wezterm: WeztermConfig,
+
+    /// Settings of terminals without remote control
+    pub terminal: TerminalConfig,
+
+    /// Policy of environment inheritance
+    pub env: EnvConfig,
+}
+
+/// Policies of environment inheritance
+///
+/// The global, provider and target policies are applied one after another,
+/// so variable is inherited only if all of them allow it.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct EnvConfig {
+    /// Patterns of variables to inherit
+    ///
+    /// Empty list means all the variables.
+    pub include: Vec<String>,
+
+    /// Patterns of variables not to inherit
+    ///
+    /// Defaults to the list of variables which are known to break new
+    /// processes, e.g. `KITTY_WINDOW_ID` or `NVIM`.
+    pub exclude: Vec<String>,
+
+    /// Policies for data taken with provider by its name, e.g. `kitty`
+    pub providers: HashMap<String, EnvPolicy>,
+
+    /// Policies for commands, e.g. `kitty`, `vim`, `run` or `env`
+    pub targets: HashMap<String, EnvPolicy>,
+
+    /// The environment to compare inherited one with
+    pub diff: EnvDiff,
+}
+
+/// Settings of kitty terminal
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct KittyConfig {
+    /// Binary to run for new kitty instance
+    pub command: String,
+
+    /// Regular expression matching the app_id of kitty windows within niri
+    pub app_id: String,
+
+    /// Template of kitty socket
+    ///
+    /// Accepts the same syntax as `--kitty-socket` argument.
+    pub socket: String,
+
+    /// Where to take cwd and environment of kitty window from
+    pub context: KittyContext,
+
+    /// Password for kitty `remote_control_password`
+    pub password: Option<String>,
+
+    /// File to read password for kitty remote control from
+    pub password_file: Option<PathBuf>,
+
+    /// Environment variable to take password for kitty remote control from
+    ///
+    /// Used when neither `password` nor `password_file` is set.
+    pub password_env: String,
+
+    /// User variable the remote shell reports its cwd with
+    ///
+    /// Set with `printf '\e]1337;SetUserVar=%s=%s\a' cwd "$(printf %s "$PWD"
+    /// | base64)"` from prompt of remote shell to open new ssh connection
+    /// in the same directory.
+    pub cwd_user_var: Option<String>,
+}
+
+/// Settings of neovide editor
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct NeovideConfig {
+    /// Binary to run for new neovide instance
+    pub command: String,
+
+    /// Regular expression matching the app_id of neovide windows within niri
+    pub app_id: String,
+
+    /// Which directory of neovim to use as cwd
+    pub context: NvimContext,
+
+    /// Template of neovim server address for project
+    ///
+    /// Accepts `${ENV}` variables and `{project}` which is replaced with hash
+    /// of project path.
+    pub listen: String,
+}
+
+/// Settings of editor launched with `vim` command
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct EditorConfig {
+    /// The frontend to run neovim with
+    pub frontend: EditorFrontend,
+
+    /// Binaries of frontends by their names, e.g. `nvim-qt = "/opt/nvim-qt"`
+    ///
+    /// Defaults to `neovide.command` for neovide, `kitty.command` for kitty
+    /// ones and to the name of frontend otherwise.
+    pub commands: HashMap<EditorFrontend, String>,
+
+    /// Binary of neovim to run within terminal frontends
+    pub nvim: String,
+
+    /// The kind of kitty window to launch neovim into with `kitty-launch`
+    pub kitty_into: KittyInto,
+}
+
+/// Settings of WezTerm terminal
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct WeztermConfig {
+    /// Binary to run for new WezTerm instance and its CLI
+    pub command: String,
+
+    /// Regular expression matching the app_id of WezTerm windows within niri
+    pub app_id: String,
+
+    /// Template of WezTerm GUI socket
+    ///
+    /// Accepts `${ENV}` variables and `{pid}` of WezTerm GUI process.
+    pub socket: String,
+}
+
+/// Settings of terminals without remote control
+///
+/// The context of such terminals is taken from the foreground process of
+/// their shell.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct TerminalConfig {
+    /// Regular expressions matching the app_id of terminal windows
+    pub app_ids: Vec<String>,
+}
+
+impl Default for KittyConfig {
+    fn default() -> Self {
+        Self {
+            command: "kitty".into(),
+            app_id: "kitty".into(),
+            socket: "${XDG_RUNTIME_DIR}/kitty-{pid}".into(),
+            context: KittyContext::default(),
+            password: None,
+            password_file: None,
+            password_env: "KITTY_RC_PASSWORD".into(),
+            cwd_user_var: Some("cwd".into()),
+        }
+    }
+}
+
+impl Default for EnvConfig {
+    fn default() -> Self {
+        let builtin = EnvPolicy::builtin();
+        Self {
+            include: builtin.include,
+            exclude: builtin.exclude,
+            providers: HashMap::new(),
+            targets: HashMap::new(),
+            diff: EnvDiff::default(),
+        }
+    }
+}
+
+impl EnvConfig {
+    /// Get the policies to apply to data of `provider` used for `target`
+    pub fn policies(
+        &self,
+        provider: Option<&str>,
+        target: &str,
+    ) -> Vec<EnvPolicy> {
+        let global = EnvPolicy {
+            include: self.include.clone(),
+            exclude: self.exclude.clone(),
+        };
+        let provider =
+            provider.and_then(|provider| self.providers.get(provider));
+        let target = self.targets.get(target);
+        [Some(&global), provider, target]
+            .into_iter()
+            .flatten()
+            .cloned()
+            .collect()
+    }
+}
+
+impl KittyConfig {
+    /// Get password for kitty remote control from the first available source
+    pub fn password(&self) -> Result<Option<String>> {
+        if let Some(password) = self.password.as_ref() {
+            return Ok(Some(password.clone()));
+        }
+        if let Some(path) = self.password_file.as_ref() {
+            let password = fs::read_to_string(path).map_err(|err| {
+                Error::Config(format!(
+                    "Failed to read {}: {err}",
+                    path.display()
+                ))
+            })?;
+            return Ok(Some(password.trim_end_matches('\n').into()));
+        }
+        Ok(env::var(&self.password_env).ok())
+    }
+}
+
+impl Default for NeovideConfig {
+    fn default() -> Self {
+        Self {
+            command: "neovide".into(),
+            app_id: "neovide".into(),
+            context: NvimContext::default(),
+            listen: "${XDG_RUNTIME_DIR}/niri-launcher/nvim-{project}.sock"
+                .into(),
+        }
+    }
+}
+
+impl Default for EditorConfig {
+    fn default() -> Self {
+        Self {
+            frontend: EditorFrontend::default(),
+            commands: HashMap::new(),
+            nvim: "nvim".into(),
+            kitty_into: KittyInto::default(),
+        }
+    }
+}
+
+impl Default for WeztermConfig {
+    fn default() -> Self {
+        Self {
+            command: "wezterm".into(),
+            app_id: "org\\.wezfurlong\\.wezterm".into(),
+            socket: "${XDG_RUNTIME_DIR}/wezterm/gui-sock-{pid}".into(),
+        }
+    }
+}
+
+impl Default for TerminalConfig {
+    fn default() -> Self {
+        Self {
+            app_ids: [
+                "foot",
+                "footclient",
+                "Alacritty",
+                "com\\.mitchellh\\.ghostty",
+                "org\\.gnome\\.Console",
+            ]
+            .map(String::from)
+            .into(),
+        }
+    }
+}
+
+impl Config {
+    /// Get the default location of configuration file
+    ///
+    /// Returns [None] if neither `XDG_CONFIG_HOME` nor `HOME` is set.
+    pub fn default_path() -> Option<PathBuf> {
+        let dir = env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| {
+                env::var_os("HOME")
+                    .map(|home| PathBuf::from(home).join(".config"))
+            })?;
+        Some(dir.join(CONFIG_DIR).join(CONFIG_FILE))
+    }
+
+    /// Load configuration
+    ///
+    /// When `path` is given the file must exist. Otherwise the file at
+    /// [default location](Self::default_path) is read if it exists and
+    /// the default configuration is returned if it does not.
+    pub fn load(path: Option<&PathBuf>) -> Result<Self> {
+        match path {
+            Some(path) => Self::read(path),
+            None => match Self::default_path() {
+                Some(path) if path.exists() => Self::read(&path),
+                _ => Ok(Self::default()),
+            },
+        }
+    }
+
+    fn read(path: &PathBuf) -> Result<Self> {
+        let content = fs::read_to_string(path).map_err(|err| {
+            Error::Config(format!("Failed to read {}: {err}", path.display()))
+        })?;
+        toml::from_str(&content).map_err(|err| {
+            Error::Config(format!("Failed to parse {}: {err}", path.display()))
+        })
+    }
+}
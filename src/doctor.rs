@@ -0,0 +1,226 @@
+//!
+//! Diagnostic report of launcher environment. Each check is performed
+//! independently, so single failure does not hide the state of the others.
+//!
+
+use crate::{
+    config::Config, niri_request, provider, Error, Launcher, ReportFormat,
+    Result,
+};
+use niri_ipc::{Request, Response};
+use serde::Serialize;
+use std::{
+    env, fs, io,
+    os::unix::fs::PermissionsExt,
+    path::{Path, PathBuf},
+};
+
+#[derive(Serialize, Debug, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+enum Status {
+    Pass,
+    Fail,
+    Skip,
+}
+
+#[derive(Serialize, Debug)]
+struct Check {
+    name: String,
+    status: Status,
+    detail: String,
+}
+
+#[derive(Default)]
+struct Report {
+    checks: Vec<Check>,
+    error: Option<Error>,
+}
+
+impl Report {
+    fn add(&mut self, name: &str, status: Status, detail: String) {
+        self.checks.push(Check {
+            name: name.into(),
+            status,
+            detail,
+        });
+    }
+
+    /// Record result of check and remember the first failure
+    fn check<T, F>(
+        &mut self,
+        name: &str,
+        result: Result<T>,
+        detail: F,
+    ) -> Option<T>
+    where
+        F: FnOnce(&T) -> String,
+    {
+        match result {
+            Ok(res) => {
+                self.add(name, Status::Pass, detail(&res));
+                Some(res)
+            }
+            Err(err) => {
+                self.add(name, Status::Fail, err.to_string());
+                self.error.get_or_insert(err);
+                None
+            }
+        }
+    }
+
+    fn skip(&mut self, name: &str, detail: &str) {
+        self.add(name, Status::Skip, detail.into());
+    }
+
+    fn print(&self, format: ReportFormat) {
+        match format {
+            ReportFormat::Table => {
+                for check in &self.checks {
+                    let status = match check.status {
+                        Status::Pass => "PASS",
+                        Status::Fail => "FAIL",
+                        Status::Skip => "SKIP",
+                    };
+                    println!("{status:<6}{:<16}{}", check.name, check.detail);
+                }
+            }
+            ReportFormat::Json => {
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&self.checks).unwrap()
+                )
+            }
+        }
+    }
+}
+
+impl Launcher {
+    /// Check everything launcher depends on and print report
+    ///
+    /// Fails with the error of the first failed check.
+    pub(crate) fn run_doctor(mut self, format: ReportFormat) -> Result<()> {
+        let mut report = Report::default();
+
+        let path = self.config.clone().or_else(Config::default_path);
+        let loaded = Config::load(self.config.as_ref()).map(|config| {
+            let detail = match path {
+                Some(path) if path.exists() => path.display().to_string(),
+                _ => "no configuration file, using defaults".into(),
+            };
+            (config, detail)
+        });
+        let (config, detail) = match loaded {
+            Ok((config, detail)) => (config, Ok(detail)),
+            Err(err) => (Config::default(), Err(err)),
+        };
+        let applied = self.apply_settings(config);
+        report.check(
+            "config",
+            detail.and_then(|detail| applied.map(|_| detail)),
+            String::clone,
+        );
+
+        for (name, command) in [
+            ("kitty binary", &self.settings.kitty.command),
+            ("neovide binary", &self.settings.neovide.command),
+        ] {
+            report.check(
+                name,
+                find_executable(command)
+                    .map(|path| path.display().to_string())
+                    .ok_or(Error::ExecFailed(io::Error::new(
+                        io::ErrorKind::NotFound,
+                        format!("{command} not found in PATH"),
+                    ))),
+                String::clone,
+            );
+        }
+
+        let socket = self.connect_niri();
+        let Some(mut socket) =
+            report.check("niri socket", socket, |_| "connected".into())
+        else {
+            report.skip("niri version", "niri is unavailable");
+            report.skip("base window", "niri is unavailable");
+            report.skip("provider", "niri is unavailable");
+            report.skip("kitty socket", "niri is unavailable");
+            report.print(format);
+            return report.error.map_or(Ok(()), Err);
+        };
+
+        let version =
+            niri_request(&mut socket, Request::Version).and_then(|response| {
+                match response {
+                    Response::Version(version) => Ok(version),
+                    _ => Err(Error::NiriProtocol(
+                        "Unexpected response to Version".into(),
+                    )),
+                }
+            });
+        report.check("niri version", version, String::clone);
+
+        let window = self.get_base_window(&mut socket).and_then(|window| {
+            window.ok_or(Error::NiriProtocol("No base window".into()))
+        });
+        let Some(window) = report.check("base window", window, |window| {
+            format!(
+                "{} {} ({})",
+                window.id,
+                window.app_id.as_deref().unwrap_or("<no app_id>"),
+                window.title.as_deref().unwrap_or_default()
+            )
+        }) else {
+            report.skip("provider", "no base window");
+            report.skip("kitty socket", "no base window");
+            report.print(format);
+            return report.error.map_or(Ok(()), Err);
+        };
+
+        let app_id = window.app_id.as_deref().unwrap_or_default();
+        let provider = self
+            .providers
+            .find(app_id)
+            .map(|provider| provider.name().to_string());
+        report.check(
+            "provider",
+            provider.ok_or(Error::ProviderUnsupported(format!(
+                "No provider for {app_id}"
+            ))),
+            String::clone,
+        );
+
+        if self.is_kitty(&window).unwrap_or(false) {
+            let found = self.kitty_provider().and_then(|kitty| {
+                kitty.discover(provider::window_pid(&window)?)
+            });
+            let found = found.and_then(|mut found| {
+                found
+                    .socket
+                    .request(crate::kitty::Command::Ls(Default::default()))?;
+                Ok(format!("{} (found with {})", found.address, found.source))
+            });
+            report.check("kitty socket", found, String::clone);
+        } else {
+            report.skip("kitty socket", "base window is not kitty");
+        }
+
+        report.print(format);
+        report.error.map_or(Ok(()), Err)
+    }
+}
+
+/// Find executable in `PATH` the same way shell does
+fn find_executable(command: &str) -> Option<PathBuf> {
+    let is_executable = |path: &Path| {
+        fs::metadata(path).is_ok_and(|meta| {
+            meta.is_file() && meta.permissions().mode() & 0o111 != 0
+        })
+    };
+    if command.contains('/') {
+        let path = PathBuf::from(command);
+        return is_executable(&path).then_some(path);
+    }
+    env::split_paths(&env::var_os("PATH")?)
+        .map(|dir| dir.join(command))
+        .find(|path| is_executable(path))
+}
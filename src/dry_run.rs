@@ -0,0 +1,67 @@
+//!
+//! Printing of commands instead of executing them.
+//!
+
+use crate::{quote, DryRunFormat};
+use serde_json::json;
+use std::{env, process::Command};
+
+/// Print program, arguments, cwd and environment changes of command
+///
+/// Only variables which differ from the launcher's own environment are
+/// printed.
+pub fn print(proc: &Command, format: DryRunFormat) {
+    let lossy = |s: &std::ffi::OsStr| s.to_string_lossy().into_owned();
+    let program = lossy(proc.get_program());
+    let args: Vec<_> = proc.get_args().map(lossy).collect();
+    let cwd = proc.get_current_dir().map(|cwd| cwd.display().to_string());
+    let mut set = Vec::new();
+    let mut unset = Vec::new();
+    for (name, val) in proc.get_envs() {
+        match val {
+            Some(val) if env::var_os(name).as_deref() != Some(val) => {
+                set.push((lossy(name), lossy(val)))
+            }
+            None if env::var_os(name).is_some() => unset.push(lossy(name)),
+            _ => (),
+        }
+    }
+    set.sort();
+    unset.sort();
+
+    match format {
+        DryRunFormat::Shell => {
+            let mut line = Vec::new();
+            if let Some(cwd) = cwd {
+                line.push(format!("cd {} &&", quote::sh(&cwd)));
+            }
+            if !set.is_empty() || !unset.is_empty() {
+                line.push("env".into());
+                line.extend(
+                    unset.iter().map(|name| format!("-u {}", quote::sh(name))),
+                );
+                line.extend(
+                    set.iter()
+                        .map(|(name, val)| quote::sh(&format!("{name}={val}"))),
+                );
+            }
+            line.push(quote::sh(&program));
+            line.extend(args.iter().map(|arg| quote::sh(arg)));
+            println!("{}", line.join(" "));
+        }
+        DryRunFormat::Json => {
+            let env: serde_json::Map<_, _> = set
+                .into_iter()
+                .map(|(name, val)| (name, json!(val)))
+                .collect();
+            let cmd = json!({
+                "program": program,
+                "args": args,
+                "cwd": cwd,
+                "env": env,
+                "unset": unset,
+            });
+            println!("{}", serde_json::to_string_pretty(&cmd).unwrap());
+        }
+    }
+}
@@ -0,0 +1,349 @@
+//!
+//! Launching of editor. Files are opened within existing neovim server of the
+//! project when there is one, otherwise new [frontend](EditorFrontend) is
+//! started with neovim listening on
+//! [predictable socket](Launcher::nvim_listen) of the project.
+//!
+
+use crate::{
+    dry_run, niri_request, niri_windows, nvim, process, provider, Command,
+    Launcher, LaunchingData, Result,
+};
+use clap::ValueEnum;
+use niri_ipc::{Action, Request};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::{
+    env, fs, io,
+    os::unix::net::UnixStream,
+    path::{Path, PathBuf},
+};
+
+/// Lua code opening files passed as arguments
+const EDIT_LUA: &str = "for _, file in ipairs({...}) do \
+                        vim.cmd.edit(vim.fn.fnameescape(file)) end";
+
+/// Lua code returning the global cwd
+const CWD_LUA: &str = "return vim.fn.getcwd(-1, -1)";
+
+/// Lua code returning pid of neovim
+const PID_LUA: &str = "return vim.fn.getpid()";
+
+/// The frontend to run neovim with
+#[derive(
+    ValueEnum, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, Hash,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum EditorFrontend {
+    /// Neovide GUI
+    #[default]
+    Neovide,
+    /// Neovim Qt GUI
+    NvimQt,
+    /// Goneovim GUI
+    Goneovim,
+    /// Neovim within new kitty instance
+    Kitty,
+    /// Neovim within new window of focused kitty instance
+    ///
+    /// Falls back to new kitty instance if base window is not kitty.
+    KittyLaunch,
+}
+
+impl EditorFrontend {
+    /// Whether neovim is run within terminal
+    fn is_terminal(self) -> bool {
+        matches!(self, Self::Kitty | Self::KittyLaunch)
+    }
+}
+
+/// The running neovim server of project
+struct Server {
+    socket: nvim::NvimSocket,
+    address: String,
+    window: Option<u64>,
+}
+
+impl Launcher {
+    pub(crate) fn run_vim(
+        &self,
+        window: Option<&niri_ipc::Window>,
+        data: LaunchingData,
+    ) -> Result<()> {
+        let Command::Vim { frontend, files } = &self.command else {
+            unreachable!("run_vim is used only for Command::Vim");
+        };
+        let frontend = frontend.unwrap_or(self.settings.editor.frontend);
+        let current = env::current_dir()?;
+        let files: Vec<_> =
+            files.iter().map(|file| current.join(file)).collect();
+        // The project of the first file wins over the one of base window
+        let project = files
+            .first()
+            .and_then(|file| file.parent())
+            .or(data.cwd.as_deref().map(Path::new))
+            .map(project_root);
+
+        if let Some(project) = project.as_ref() {
+            if let Some(server) = self.find_nvim_server(project, frontend) {
+                return self.edit_within(server, &files);
+            }
+        }
+
+        let mut nvim_args = Vec::new();
+        let listen = project.as_ref().and_then(|dir| self.nvim_listen(dir));
+        if let Some(listen) = listen {
+            if self.dry_run.is_none() {
+                if let Some(dir) = listen.parent() {
+                    fs::create_dir_all(dir)?;
+                }
+                remove_stale_socket(&listen)?;
+            }
+            nvim_args.push("--listen".into());
+            nvim_args.push(listen.to_string_lossy().into_owned());
+        }
+
+        if !frontend.is_terminal() {
+            let mut proc =
+                std::process::Command::new(self.editor_command(frontend));
+            proc.args(&files);
+            // The GUI frontends pass arguments after `--` to neovim
+            if !nvim_args.is_empty() {
+                proc.arg("--").args(nvim_args);
+            }
+            return self.exec_within(window, proc, data);
+        }
+
+        let args: Vec<_> = [self.settings.editor.nvim.clone()]
+            .into_iter()
+            .chain(nvim_args)
+            .chain(files.iter().map(|file| file.to_string_lossy().into()))
+            .collect();
+        if let (EditorFrontend::KittyLaunch, Some(window)) = (frontend, window)
+        {
+            let into = self.settings.editor.kitty_into;
+            if self
+                .launch_into_kitty(window, into, None, &data, args.clone())
+                .is_ok()
+            {
+                return Ok(());
+            }
+        }
+        let mut proc = self.kitty_command(self.editor_command(frontend), data);
+        proc.args(args);
+        self.exec(window, proc)
+    }
+
+    /// Get binary of editor `frontend`
+    fn editor_command(&self, frontend: EditorFrontend) -> &str {
+        if let Some(command) = self.settings.editor.commands.get(&frontend) {
+            return command;
+        }
+        match frontend {
+            EditorFrontend::Neovide => &self.settings.neovide.command,
+            EditorFrontend::NvimQt => "nvim-qt",
+            EditorFrontend::Goneovim => "goneovim",
+            EditorFrontend::Kitty | EditorFrontend::KittyLaunch => {
+                &self.settings.kitty.command
+            }
+        }
+    }
+
+    /// Get regex matching app_id of windows of editor `frontend`
+    fn editor_app_id(&self, frontend: EditorFrontend) -> &str {
+        match frontend {
+            EditorFrontend::Neovide => &self.settings.neovide.app_id,
+            EditorFrontend::NvimQt => "nvim-qt",
+            EditorFrontend::Goneovim => "goneovim",
+            EditorFrontend::Kitty | EditorFrontend::KittyLaunch => {
+                &self.settings.kitty.app_id
+            }
+        }
+    }
+
+    /// Get predictable address of neovim server for `project`
+    ///
+    /// The `{project}` of `neovide.listen` template is replaced with hash of
+    /// project path to fit into limit of unix socket path length. Returns
+    /// [None] if variables of template are not set.
+    pub(crate) fn nvim_listen(&self, project: &Path) -> Option<PathBuf> {
+        let project = canonical(project);
+        let hash = Sha256::digest(project.as_os_str().as_encoded_bytes());
+        let hash: String =
+            hash[..8].iter().map(|byte| format!("{byte:02x}")).collect();
+        let listen = provider::try_expand_env(&self.settings.neovide.listen)?;
+        Some(listen.replace("{project}", &hash).into())
+    }
+
+    /// Find running neovim server of `project`
+    ///
+    /// Checks the predictable socket first and then asks neovim within each
+    /// window of `frontend` for its cwd.
+    fn find_nvim_server(
+        &self,
+        project: &Path,
+        frontend: EditorFrontend,
+    ) -> Option<Server> {
+        let windows = self
+            .connect_niri()
+            .and_then(|mut socket| niri_windows(&mut socket))
+            .unwrap_or_default();
+        let window_of = |pid: i32| {
+            process::ancestors(pid).find_map(|pid| {
+                windows.iter().find(|window| window.pid == Some(pid))
+            })
+        };
+
+        let listen = self.nvim_listen(project);
+        let listen = listen.as_deref().map(Path::to_string_lossy);
+        let socket = listen
+            .as_ref()
+            .and_then(|listen| nvim::NvimSocket::connect(listen).ok());
+        if let (Some(listen), Some(mut socket)) = (listen, socket) {
+            let pid = socket.exec_lua(PID_LUA, vec![]).ok();
+            let pid = pid.and_then(|pid| pid.as_i64());
+            return Some(Server {
+                socket,
+                address: listen.into_owned(),
+                window: pid
+                    .and_then(|pid| window_of(pid as i32))
+                    .map(|window| window.id),
+            });
+        }
+
+        let app_id =
+            provider::app_id_pattern(self.editor_app_id(frontend)).ok()?;
+        windows
+            .iter()
+            .filter(|window| {
+                app_id.is_match(window.app_id.as_deref().unwrap_or_default())
+            })
+            .find_map(|window| {
+                let pid = nvim::find_process(window.pid?)?;
+                let address = nvim::server_address(pid)?;
+                let mut socket = nvim::NvimSocket::connect(&address).ok()?;
+                let cwd = socket.exec_lua(CWD_LUA, vec![]).ok()?;
+                let root = project_root(Path::new(cwd.as_str()?));
+                (root == project).then_some(Server {
+                    socket,
+                    address,
+                    window: Some(window.id),
+                })
+            })
+    }
+
+    /// Open `files` within neovim `server` and focus its window
+    fn edit_within(&self, mut server: Server, files: &[PathBuf]) -> Result<()> {
+        if let Some(format) = self.dry_run {
+            let mut proc = std::process::Command::new("nvim");
+            proc.args(["--server", &server.address, "--remote"])
+                .args(files);
+            dry_run::print(&proc, format);
+            return Ok(());
+        }
+        let files = files
+            .iter()
+            .map(|file| file.to_string_lossy().into_owned().into())
+            .collect();
+        server.socket.exec_lua(EDIT_LUA, files)?;
+        if let Some(id) = server.window {
+            niri_request(
+                &mut self.connect_niri()?,
+                Request::Action(Action::FocusWindow { id }),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Resolve `..`, `.` and symlinks of `path` if it exists
+fn canonical(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or(path.into())
+}
+
+/// Get project of directory `dir`, i.e. its git root or itself
+///
+/// The path is canonical, so the same project is found by any path to it.
+fn project_root(dir: &Path) -> PathBuf {
+    let dir = canonical(dir);
+    provider::git_root(&dir).unwrap_or(dir)
+}
+
+/// Remove socket left by crashed or killed neovim at `path`
+///
+/// Otherwise new neovim fails to listen on it.
+fn remove_stale_socket(path: &Path) -> Result<()> {
+    match UnixStream::connect(path) {
+        Err(err) if err.kind() == io::ErrorKind::ConnectionRefused => {
+            Ok(fs::remove_file(path)?)
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+    use std::os::unix::net::UnixListener;
+
+    #[test]
+    fn equivalent_projects_share_socket() {
+        let dir = env::temp_dir()
+            .join(format!("niri-launcher-test-{}-listen", std::process::id()));
+        fs::create_dir_all(dir.join("project/sub")).unwrap();
+        let link = dir.join("link");
+        let _ = fs::remove_file(&link);
+        std::os::unix::fs::symlink(dir.join("project"), &link).unwrap();
+
+        let mut launcher = Launcher::parse_from(["niri-launcher", "vim"]);
+        launcher.settings.neovide.listen = "/run/nvim-{project}.sock".into();
+        let listen = |path: PathBuf| launcher.nvim_listen(&path).unwrap();
+        let expected = listen(dir.join("project"));
+        assert_eq!(listen(dir.join("project/sub/..")), expected);
+        assert_eq!(listen(dir.join("./project/.")), expected);
+        assert_eq!(listen(link.clone()), expected);
+        assert_ne!(listen(dir.join("project/sub")), expected);
+        assert_eq!(
+            project_root(&dir.join("project/sub/../sub")),
+            canonical(&dir.join("project/sub"))
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn editor_command_is_per_frontend() {
+        let mut launcher = Launcher::parse_from(["niri-launcher", "vim"]);
+        launcher.settings = toml::from_str(
+            "[kitty]\ncommand = \"/opt/kitty\"\n\
+             [editor]\nfrontend = \"neovide\"\n\
+             commands = { neovide = \"/opt/neovide\", kitty = \"kitty-nvim\" }",
+        )
+        .unwrap();
+        let command = |frontend| launcher.editor_command(frontend);
+        assert_eq!(command(EditorFrontend::Neovide), "/opt/neovide");
+        assert_eq!(command(EditorFrontend::NvimQt), "nvim-qt");
+        assert_eq!(command(EditorFrontend::Kitty), "kitty-nvim");
+        assert_eq!(command(EditorFrontend::KittyLaunch), "/opt/kitty");
+    }
+
+    #[test]
+    fn stale_socket_is_removed() {
+        let dir = env::temp_dir()
+            .join(format!("niri-launcher-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("nvim.sock");
+
+        let listener = UnixListener::bind(&path).unwrap();
+        remove_stale_socket(&path).unwrap();
+        assert!(path.exists(), "socket of running server is kept");
+
+        drop(listener);
+        remove_stale_socket(&path).unwrap();
+        assert!(!path.exists(), "socket without server is removed");
+
+        remove_stale_socket(&path).unwrap();
+        fs::remove_dir(&dir).unwrap();
+    }
+}
@@ -0,0 +1,113 @@
+//!
+//! The baseline environment to compare inherited one with. Only the
+//! variables which differ from baseline are passed to new process.
+//!
+
+use crate::{Error, Result};
+use clap::ValueEnum;
+use serde::Deserialize;
+use std::{collections::HashMap, env, io, process::Command};
+
+/// The environment to compare inherited variables with
+#[derive(ValueEnum, Deserialize, Debug, Clone, Copy, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum EnvDiff {
+    /// Pass all the inherited variables
+    #[default]
+    Off,
+    /// Pass only variables which differ from launcher's own environment
+    Launcher,
+    /// Pass only variables which differ from systemd user manager
+    /// environment
+    Systemd,
+}
+
+impl EnvDiff {
+    /// Get the baseline environment or [None] if diff is off
+    pub fn baseline(self) -> Result<Option<HashMap<String, String>>> {
+        match self {
+            Self::Off => Ok(None),
+            Self::Launcher => Ok(Some(env::vars().collect())),
+            Self::Systemd => systemd_environment().map(Some),
+        }
+    }
+}
+
+/// Read environment of systemd user manager
+fn systemd_environment() -> Result<HashMap<String, String>> {
+    let output = Command::new("systemctl")
+        .args(["--user", "show-environment"])
+        .output()?;
+    if !output.status.success() {
+        return Err(Error::Io(io::Error::other(
+            "systemctl --user show-environment failed",
+        )));
+    }
+    Ok(parse_environment(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Parse `NAME=VALUE` lines printed by `systemctl show-environment`
+fn parse_environment(output: &str) -> HashMap<String, String> {
+    output
+        .lines()
+        .filter_map(|line| line.split_once('='))
+        .map(|(name, val)| (name.into(), unescape(val)))
+        .collect()
+}
+
+/// Unescape value printed by systemctl in `$'...'` form
+fn unescape(value: &str) -> String {
+    let Some(value) = value
+        .strip_prefix("$'")
+        .and_then(|value| value.strip_suffix('\''))
+    else {
+        return value.into();
+    };
+    let mut res = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            res.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => res.push('\n'),
+            Some('t') => res.push('\t'),
+            Some('r') => res.push('\r'),
+            Some(c) => res.push(c),
+            None => res.push('\\'),
+        }
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unescape_plain() {
+        assert_eq!(unescape("/usr/bin:/bin"), "/usr/bin:/bin");
+        assert_eq!(unescape("a=b"), "a=b");
+        assert_eq!(unescape("$'plain'"), "plain");
+    }
+
+    #[test]
+    fn unescape_sequences() {
+        assert_eq!(unescape(r"$'a\nb\tc\rd'"), "a\nb\tc\rd");
+        assert_eq!(unescape(r"$'C:\\dir'"), r"C:\dir");
+        assert_eq!(unescape(r"$'it\'s'"), "it's");
+        assert_eq!(unescape(r"$'trailing\'"), r"trailing\");
+    }
+
+    #[test]
+    fn parse_lines() {
+        let env = parse_environment(
+            "HOME=/home/user\nPS1=$'\\\\u@\\\\h\\n'\nEQ=a=b\ninvalid\n",
+        );
+        assert_eq!(env.len(), 3);
+        assert_eq!(env["HOME"], "/home/user");
+        assert_eq!(env["PS1"], "\\u@\\h\n");
+        assert_eq!(env["EQ"], "a=b");
+    }
+}
@@ -0,0 +1,92 @@
+//!
+//! Printing of [LaunchingData] in formats suitable for other tools.
+//!
+
+use crate::{quote, EnvFormat, LaunchingData};
+use serde_json::json;
+use std::io::{self, Write};
+
+/// Write cwd, provider and environment of `data` to stdout
+pub fn print(data: &LaunchingData, format: EnvFormat) -> io::Result<()> {
+    let mut env: Vec<_> = data.env.iter().collect();
+    env.sort();
+    let mut out = io::stdout().lock();
+
+    match format {
+        EnvFormat::Sh | EnvFormat::Fish | EnvFormat::Systemd => {
+            if let Some(provider) = data.provider.as_ref() {
+                writeln!(out, "# provider: {provider}")?;
+            }
+        }
+        _ => (),
+    }
+
+    match format {
+        EnvFormat::Sh => {
+            if let Some(cwd) = data.cwd.as_ref() {
+                writeln!(out, "cd {}", quote::sh(cwd))?;
+            }
+            for (name, val) in env.iter().filter(|(name, _)| shell_name(name)) {
+                writeln!(out, "export {name}={}", quote::sh(val))?;
+            }
+            for name in data.unset_env.iter().filter(|name| shell_name(name)) {
+                writeln!(out, "unset {name}")?;
+            }
+        }
+        EnvFormat::Fish => {
+            if let Some(cwd) = data.cwd.as_ref() {
+                writeln!(out, "cd {}", quote::fish(cwd))?;
+            }
+            for (name, val) in env.iter().filter(|(name, _)| shell_name(name)) {
+                writeln!(out, "set -gx {name} {}", quote::fish(val))?;
+            }
+            for name in data.unset_env.iter().filter(|name| shell_name(name)) {
+                writeln!(out, "set -e {name}")?;
+            }
+        }
+        EnvFormat::Systemd => {
+            if let Some(cwd) = data.cwd.as_ref() {
+                writeln!(out, "# cwd: {cwd}")?;
+            }
+            for (name, val) in env {
+                writeln!(out, "{name}={}", quote::systemd(val))?;
+            }
+            for name in &data.unset_env {
+                writeln!(out, "# unset: {name}")?;
+            }
+        }
+        EnvFormat::Json => {
+            let data = json!({
+                "provider": data.provider,
+                "cwd": data.cwd,
+                "env": data.env,
+                "unset": data.unset_env,
+            });
+            writeln!(out, "{}", serde_json::to_string_pretty(&data)?)?;
+        }
+        EnvFormat::Null => {
+            write!(out, "{}\0", data.cwd.as_deref().unwrap_or_default())?;
+            write!(out, "{}\0", data.provider.as_deref().unwrap_or_default())?;
+            for (name, val) in env {
+                write!(out, "{name}={val}\0")?;
+            }
+            // Records without `=` are variables to unset
+            for name in &data.unset_env {
+                write!(out, "{name}\0")?;
+            }
+        }
+    }
+    out.flush()
+}
+
+/// Whether variable `name` may be written for shell
+///
+/// Reports the names which are not identifiers, e.g. `BASH_FUNC_foo%%` of
+/// exported bash function, as these break evaluation of whole output.
+fn shell_name(name: &str) -> bool {
+    let valid = quote::is_identifier(name);
+    if !valid {
+        eprintln!("niri-launcher: skipping variable {name:?} invalid in shell");
+    }
+    valid
+}
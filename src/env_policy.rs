@@ -0,0 +1,128 @@
+//!
+//! Filtering of inherited environment. The [EnvPolicy] keeps the variables
+//! which match any of `include` glob patterns and none of `exclude` ones.
+//!
+
+use serde::Deserialize;
+
+/// The variables which break new processes when inherited
+pub const DEFAULT_EXCLUDE: &[&str] = &[
+    "KITTY_WINDOW_ID",
+    "KITTY_PID",
+    "KITTY_PUBLIC_KEY",
+    "KITTY_LISTEN_ON",
+    "WINDOWID",
+    "NVIM",
+    "NVIM_LISTEN_ADDRESS",
+    "NEOVIDE_*",
+    "MYVIMRC",
+    "VIM",
+    "VIMRUNTIME",
+    "WAYLAND_SOCKET",
+    "TMUX",
+    "TMUX_PANE",
+    "ZELLIJ*",
+    "SHLVL",
+    "PWD",
+    "OLDPWD",
+    "_",
+];
+
+/// Glob based filter of environment variables
+///
+/// Patterns support `*` for any sequence and `?` for any single character.
+/// Empty `include` list keeps all the variables.
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct EnvPolicy {
+    /// Patterns of variables to keep
+    pub include: Vec<String>,
+
+    /// Patterns of variables to drop
+    pub exclude: Vec<String>,
+}
+
+impl EnvPolicy {
+    /// Policy which drops variables known to break new processes
+    pub fn builtin() -> Self {
+        Self {
+            include: Vec::new(),
+            exclude: DEFAULT_EXCLUDE.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    /// Check whether variable `name` passes the policy
+    pub fn allows(&self, name: &str) -> bool {
+        (self.include.is_empty()
+            || self.include.iter().any(|pattern| glob_match(pattern, name)))
+            && !self.exclude.iter().any(|pattern| glob_match(pattern, name))
+    }
+}
+
+/// Match `name` against glob `pattern`
+///
+/// Runs on every launch for each pair of pattern and variable, so it works
+/// without compiling regular expressions. After mismatch the last `*` is
+/// retried to consume one more character.
+fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // Pattern position after the last `*` and name position it matched at
+    let mut star = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                p += 1;
+                star = Some((p, n));
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                Some((star_p, star_n)) => {
+                    p = star_p;
+                    n = star_n + 1;
+                    star = Some((star_p, n));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn glob() {
+        assert!(glob_match("PATH", "PATH"));
+        assert!(!glob_match("PATH", "PATHS"));
+        assert!(!glob_match("PATH", "PAT"));
+        assert!(glob_match("NEOVIDE_*", "NEOVIDE_"));
+        assert!(glob_match("NEOVIDE_*", "NEOVIDE_FRAME"));
+        assert!(!glob_match("NEOVIDE_*", "NEOVIDE"));
+        assert!(glob_match("*", ""));
+        assert!(glob_match("*_ID", "KITTY_WINDOW_ID"));
+        assert!(glob_match("*A*B*", "xAyAzBw"));
+        assert!(!glob_match("*A*B", "xAyBz"));
+        assert!(glob_match("K?TTY", "KITTY"));
+        assert!(!glob_match("K?TTY", "KTTY"));
+        assert!(glob_match("a.b", "a.b"));
+        assert!(!glob_match("a.b", "axb"));
+        assert!(glob_match("BASH_FUNC_*%%", "BASH_FUNC_foo%%"));
+    }
+
+    #[test]
+    fn builtin_policy() {
+        let policy = EnvPolicy::builtin();
+        assert!(policy.allows("PATH"));
+        assert!(!policy.allows("KITTY_WINDOW_ID"));
+        assert!(!policy.allows("ZELLIJ_SESSION_NAME"));
+        assert!(!policy.allows("_"));
+        assert!(policy.allows("__"));
+    }
+}
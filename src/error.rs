@@ -0,0 +1,113 @@
+//!
+//! The crate error type. Each [Error] kind has its own
+//! [process exit code](Error::exit_code) so scripts may tell failures apart.
+//!
+
+use std::{fmt, io};
+
+/// Result with crate [Error]
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// The error of launcher
+#[derive(Debug)]
+pub enum Error {
+    /// Generic input/output error
+    Io(io::Error),
+
+    /// Configuration file or arguments are invalid
+    Config(String),
+
+    /// Failed to connect to niri or to talk with it
+    NiriConnection(io::Error),
+
+    /// Niri returned error or unexpected response
+    NiriProtocol(String),
+
+    /// Failed to connect to kitty or to talk with it
+    KittyTransport(io::Error),
+
+    /// Kitty refused to perform command
+    KittyRejected(String),
+
+    /// The `wezterm cli` command failed or returned invalid output
+    WeztermFailed(String),
+
+    /// Failed to connect to neovim or to talk with it
+    NvimTransport(io::Error),
+
+    /// Neovim returned error for request
+    NvimRejected(String),
+
+    /// There is no provider able to get context from window
+    ProviderUnsupported(String),
+
+    /// Failed to execute launching process
+    ExecFailed(io::Error),
+}
+
+impl Error {
+    /// Get exit code of process failed with this error
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            Self::Io(_) => 1,
+            // The 2 is used by clap for invalid arguments
+            Self::Config(_) => 3,
+            Self::NiriConnection(_) => 4,
+            Self::NiriProtocol(_) => 5,
+            Self::KittyTransport(_) => 6,
+            Self::KittyRejected(_) => 7,
+            Self::ProviderUnsupported(_) => 8,
+            Self::ExecFailed(_) => 9,
+            Self::WeztermFailed(_) => 10,
+            Self::NvimTransport(_) => 11,
+            Self::NvimRejected(_) => 12,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "{err}"),
+            Self::Config(msg) => write!(f, "Invalid configuration: {msg}"),
+            Self::NiriConnection(err) => {
+                write!(f, "Failed to communicate with niri: {err}")
+            }
+            Self::NiriProtocol(msg) => write!(f, "Niri error: {msg}"),
+            Self::KittyTransport(err) => {
+                write!(f, "Failed to communicate with kitty: {err}")
+            }
+            Self::KittyRejected(msg) => {
+                write!(f, "Kitty rejected command: {msg}")
+            }
+            Self::WeztermFailed(msg) => write!(f, "WezTerm failed: {msg}"),
+            Self::NvimTransport(err) => {
+                write!(f, "Failed to communicate with neovim: {err}")
+            }
+            Self::NvimRejected(msg) => {
+                write!(f, "Neovim rejected request: {msg}")
+            }
+            Self::ProviderUnsupported(msg) => write!(f, "{msg}"),
+            Self::ExecFailed(err) => write!(f, "Failed to execute: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(err)
+            | Self::NiriConnection(err)
+            | Self::KittyTransport(err)
+            | Self::NvimTransport(err)
+            | Self::ExecFailed(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(value: io::Error) -> Self {
+        Self::Io(value)
+    }
+}
@@ -1,11 +1,16 @@
+use crate::{stream::Stream, Error, Result};
 use serde::{Deserialize, Serialize};
 use std::io::{prelude::*, BufReader, BufWriter};
 use std::{
-    collections, io,
-    os::unix::net::UnixStream,
-    path::{Path, PathBuf},
+    collections, env, io,
+    net::TcpStream,
+    os::linux::net::SocketAddrExt,
+    os::unix::net::{SocketAddr, UnixStream},
+    path::PathBuf,
 };
 
+mod crypto;
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct CommandPacked {
     pub cmd: String,
@@ -23,7 +28,7 @@ pub type KittyVersion = Vec<i8>;
 pub enum Command {
     Action(Action),
     Ls(Ls),
-    Launch(Launch),
+    Launch(Box<Launch>),
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
@@ -117,6 +122,34 @@ pub struct Launch {
     pub bias: Option<f32>,
 }
 
+impl Launch {
+    /// Arguments of `kitty @ launch` equivalent to this request
+    ///
+    /// Only type, location, cwd, env and args are supported.
+    pub fn to_args(&self) -> Vec<String> {
+        let name = |value: serde_json::Value| {
+            value.as_str().map(String::from).unwrap_or_default()
+        };
+        let mut args = Vec::new();
+        if let Some(launch_type) = self.launch_type.as_ref() {
+            let launch_type = serde_json::to_value(launch_type).unwrap();
+            args.push(format!("--type={}", name(launch_type)));
+        }
+        if let Some(location) = self.location.as_ref() {
+            let location = serde_json::to_value(location).unwrap();
+            args.push(format!("--location={}", name(location)));
+        }
+        if let Some(cwd) = self.cwd.as_ref() {
+            args.push(format!("--cwd={}", cwd.display()));
+        }
+        let mut env = self.env.clone().unwrap_or_default();
+        env.sort();
+        args.extend(env.into_iter().map(|env| format!("--env={env}")));
+        args.extend(self.args.iter().cloned());
+        args
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "kebab-case")]
 pub enum LaunchType {
@@ -162,9 +195,24 @@ pub struct Tab {
 pub struct Window {
     pub is_active: bool,
     pub is_focused: bool,
+    #[serde(default)]
+    pub pid: Option<i32>,
     pub cmdline: Vec<String>,
     pub cwd: PathBuf,
     pub env: std::collections::HashMap<String, String>,
+    #[serde(default)]
+    pub foreground_processes: Vec<Process>,
+    #[serde(default)]
+    pub user_vars: std::collections::HashMap<String, String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Process {
+    pub pid: i32,
+    #[serde(default)]
+    pub cwd: Option<PathBuf>,
+    #[serde(default)]
+    pub cmdline: Vec<String>,
 }
 
 impl From<Command> for CommandPacked {
@@ -188,35 +236,119 @@ impl From<Command> for CommandPacked {
 }
 
 pub struct KittySocket {
-    socket: UnixStream,
+    socket: Stream,
+    encrypter: Option<crypto::Encrypter>,
 }
 
 impl KittySocket {
-    pub fn connect(socket: impl AsRef<Path>) -> io::Result<Self> {
+    /// Connect to kitty by address in `listen_on` syntax
+    ///
+    /// Supports `unix:/path`, `unix:@abstract` and `tcp:host:port` forms.
+    /// Address without scheme is treated as path to unix socket. Relative
+    /// paths are resolved against temporary directory the same way kitty
+    /// does.
+    pub fn connect(address: &str) -> Result<Self> {
         Ok(Self {
-            socket: UnixStream::connect(socket)?,
+            socket: Self::open(address).map_err(Error::KittyTransport)?,
+            encrypter: None,
         })
     }
 
-    fn send_with(&mut self, cmd: Command, no_response: bool) -> io::Result<()> {
+    fn open(address: &str) -> io::Result<Stream> {
+        Ok(if let Some(host) = address.strip_prefix("tcp:") {
+            Stream::Tcp(TcpStream::connect(host)?)
+        } else {
+            let path = address.strip_prefix("unix:").unwrap_or(address);
+            if let Some(name) = path.strip_prefix('@') {
+                let addr = SocketAddr::from_abstract_name(name)?;
+                Stream::Unix(UnixStream::connect_addr(&addr)?)
+            } else {
+                Stream::Unix(UnixStream::connect(env::temp_dir().join(path))?)
+            }
+        })
+    }
+
+    /// Encrypt commands for kitty with `remote_control_password`
+    ///
+    /// The `public_key` is the value of `KITTY_PUBLIC_KEY` variable kitty
+    /// sets for its children.
+    pub fn with_password(
+        mut self,
+        password: String,
+        public_key: &str,
+    ) -> Result<Self> {
+        self.encrypter = Some(
+            crypto::Encrypter::new(password, public_key)
+                .map_err(Error::KittyTransport)?,
+        );
+        Ok(self)
+    }
+
+    fn send(&mut self, cmd: Command) -> io::Result<()> {
         let mut cmd = CommandPacked::from(cmd);
-        cmd.no_response = Some(no_response);
+        cmd.no_response = Some(false);
+        let mut cmd = serde_json::to_value(&cmd)?;
+        if let Some(encrypter) = self.encrypter.as_ref() {
+            cmd = encrypter.encrypt(cmd)?;
+        }
         let cmd = serde_json::to_string(&cmd).unwrap();
         {
             let mut writer = BufWriter::new(&mut self.socket);
             //let mut writer = BufWriter::new(std::fs::File::create("/tmp/test.txt")?);
-            writer.write_all(&vec![0x1b])?;
+            writer.write_all(&[0x1b])?;
             writer.write_all(b"P@kitty-cmd")?;
             writer.write_all(cmd.as_bytes())?;
-            writer.write_all(&vec![0x1b])?;
+            writer.write_all(&[0x1b])?;
             writer.write_all(b"\\")?;
         }
 
         Ok(())
     }
 
-    pub fn request(&mut self, cmd: Command) -> io::Result<serde_json::Value> {
-        self.send_with(cmd, false)?;
+    pub fn request(&mut self, cmd: Command) -> Result<serde_json::Value> {
+        self.send(cmd).map_err(Error::KittyTransport)?;
+        let mut rsp = self.receive().map_err(Error::KittyTransport)?;
+        let invalid = |msg: &str| {
+            Error::KittyTransport(io::Error::new(
+                io::ErrorKind::InvalidData,
+                msg,
+            ))
+        };
+        let ok = rsp.remove("ok");
+        if let Some(ok) = ok {
+            if ok != true {
+                let error = rsp
+                    .remove("error")
+                    .and_then(|error| error.as_str().map(String::from))
+                    .unwrap_or_default();
+                Err(Error::KittyRejected(error))
+            } else {
+                let data = rsp.remove("data");
+                if let Some(data) = data {
+                    if let Some(data) = data.as_str() {
+                        serde_json::from_str(data).map_err(|_| {
+                            invalid(
+                                "Kitty returns invalid json in 'data' field",
+                            )
+                        })
+                    } else {
+                        Err(invalid(
+                            "Kitty returns invalid data in 'data' field",
+                        ))
+                    }
+                } else {
+                    // Commands like `action` have nothing to respond with
+                    Ok(serde_json::Value::Null)
+                }
+            }
+        } else {
+            Err(invalid("Kitty returns invalid response w/o 'ok' field"))
+        }
+    }
+
+    fn receive(
+        &mut self,
+    ) -> io::Result<collections::HashMap<String, serde_json::Value>> {
         let mut reader = BufReader::new(&mut self.socket);
         let mut esc = [0; 12];
         reader.read_exact(&mut esc)?;
@@ -256,42 +388,6 @@ impl KittySocket {
             }
         }
 
-        let mut rsp: collections::HashMap<String, serde_json::Value> =
-            serde_json::from_str(&data)?;
-        let ok = rsp.remove("ok");
-        if let Some(ok) = ok {
-            if ok != true {
-                Err(io::Error::new(
-                    io::ErrorKind::Other,
-                    "Got error from kitty",
-                ))
-            } else {
-                let data = rsp.remove("data");
-                if let Some(data) = data {
-                    if let Some(data) = data.as_str() {
-                        Ok(serde_json::from_str(&data)?)
-                    } else {
-                        Err(io::Error::new(
-                            io::ErrorKind::InvalidData,
-                            "Kitty returns invalid data in 'data' field",
-                        ))
-                    }
-                } else {
-                    Err(io::Error::new(
-                        io::ErrorKind::InvalidData,
-                        "Kitty returns invalid response w/o 'data' field",
-                    ))
-                }
-            }
-        } else {
-            Err(io::Error::new(
-                io::ErrorKind::InvalidData,
-                "Kitty returns invalid response w/o 'ok' field",
-            ))
-        }
-    }
-
-    pub fn send(&mut self, cmd: Command) -> io::Result<()> {
-        self.send_with(cmd, true)
+        Ok(serde_json::from_str(&data)?)
     }
 }
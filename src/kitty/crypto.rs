@@ -0,0 +1,218 @@
+//!
+//! Encryption of kitty remote control commands for password protected
+//! instances. The command is encrypted with AES-256-GCM using the key derived
+//! with X25519 from ephemeral secret and kitty public key.
+//!
+
+use aes_gcm::aead::{AeadInPlace, KeyInit, OsRng};
+use aes_gcm::{AeadCore, Aes256Gcm};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::io;
+use std::time::{SystemTime, UNIX_EPOCH};
+use x25519_dalek::{EphemeralSecret, PublicKey};
+
+/// The encryption protocol version supported
+const ENCRYPTION_VERSION: &str = "1";
+
+/// The base85 alphabet as used by python `base64.b85encode`
+const B85_ALPHABET: &[u8; 85] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ\
+abcdefghijklmnopqrstuvwxyz!#$%&()*+-;<=>?@^_`{|}~";
+
+pub struct Encrypter {
+    password: String,
+    secret: [u8; 32],
+    public: PublicKey,
+}
+
+impl Encrypter {
+    /// Create encrypter with kitty public key from `KITTY_PUBLIC_KEY`
+    pub fn new(password: String, kitty_key: &str) -> io::Result<Self> {
+        let invalid = |msg: &str| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Invalid kitty public key: {msg}"),
+            )
+        };
+        let (version, key) = kitty_key
+            .split_once(':')
+            .ok_or_else(|| invalid("no version"))?;
+        if version != ENCRYPTION_VERSION {
+            return Err(invalid("unsupported version"));
+        }
+        let key: [u8; 32] = b85decode(key)
+            .ok_or_else(|| invalid("bad encoding"))?
+            .try_into()
+            .map_err(|_| invalid("bad length"))?;
+
+        let ephemeral = EphemeralSecret::random_from_rng(OsRng);
+        let public = PublicKey::from(&ephemeral);
+        let shared = ephemeral.diffie_hellman(&PublicKey::from(key));
+        let secret = Sha256::digest(shared.as_bytes()).into();
+        Ok(Self {
+            password,
+            secret,
+            public,
+        })
+    }
+
+    /// Wrap serialized command into encrypted envelope
+    pub fn encrypt(&self, mut cmd: Value) -> io::Result<Value> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(io::Error::other)?
+            .as_nanos();
+        let version = cmd["version"].clone();
+        cmd["password"] = json!(self.password);
+        cmd["timestamp"] = json!(timestamp as u64);
+
+        let mut data = serde_json::to_vec(&cmd)?;
+        let cipher = Aes256Gcm::new(&self.secret.into());
+        let iv = Aes256Gcm::generate_nonce(&mut OsRng);
+        let tag = cipher
+            .encrypt_in_place_detached(&iv, b"", &mut data)
+            .map_err(|_| io::Error::other("Failed to encrypt kitty command"))?;
+
+        Ok(json!({
+            "version": version,
+            "iv": b85encode(&iv),
+            "tag": b85encode(&tag),
+            "pubkey": b85encode(self.public.as_bytes()),
+            "encrypted": b85encode(&data),
+        }))
+    }
+}
+
+fn b85encode(data: &[u8]) -> String {
+    let padding = (4 - data.len() % 4) % 4;
+    let mut res = Vec::with_capacity((data.len() + padding) / 4 * 5);
+    for chunk in data.chunks(4) {
+        let mut word = [0; 4];
+        word[..chunk.len()].copy_from_slice(chunk);
+        let mut word = u32::from_be_bytes(word);
+        let mut chars = [0; 5];
+        for c in chars.iter_mut().rev() {
+            *c = B85_ALPHABET[(word % 85) as usize];
+            word /= 85;
+        }
+        res.extend_from_slice(&chars);
+    }
+    res.truncate(res.len() - padding);
+    String::from_utf8(res).unwrap()
+}
+
+fn b85decode(data: &str) -> Option<Vec<u8>> {
+    let data = data.as_bytes();
+    let padding = (5 - data.len() % 5) % 5;
+    let mut res = Vec::with_capacity((data.len() + padding) / 5 * 4);
+    for chunk in data.chunks(5) {
+        let mut word: u32 = 0;
+        for i in 0..5 {
+            let digit = match chunk.get(i) {
+                Some(c) => B85_ALPHABET.iter().position(|a| a == c)? as u32,
+                None => 84,
+            };
+            word = word.checked_mul(85)?.checked_add(digit)?;
+        }
+        res.extend_from_slice(&word.to_be_bytes());
+    }
+    res.truncate(res.len() - padding);
+    Some(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aes_gcm::{Nonce, Tag};
+
+    /// Pairs of data and its encoding by python `base64.b85encode`
+    const VECTORS: &[(&[u8], &str)] = &[
+        (b"", ""),
+        (b"a", "VE"),
+        (b"ab", "VPX"),
+        (b"abc", "VPaz"),
+        (b"abcd", "VPa!s"),
+        (b"abcde", "VPa!sWd"),
+        (b"\0\0\0\0", "00000"),
+        (b"\xff\xff\xff\xff\xff", "|NsC0{{"),
+    ];
+
+    fn kitty_key(data: &[u8]) -> String {
+        format!("{ENCRYPTION_VERSION}:{}", b85encode(data))
+    }
+
+    #[test]
+    fn b85encode_matches_python() {
+        for (data, encoded) in VECTORS {
+            assert_eq!(b85encode(data), *encoded);
+        }
+    }
+
+    #[test]
+    fn b85decode_matches_python() {
+        for (data, encoded) in VECTORS {
+            assert_eq!(b85decode(encoded).as_deref(), Some(*data));
+        }
+    }
+
+    #[test]
+    fn b85_round_trip() {
+        let data: Vec<u8> = (0..=255).collect();
+        for len in 0..data.len() {
+            let encoded = b85encode(&data[..len]);
+            assert_eq!(b85decode(&encoded).as_deref(), Some(&data[..len]));
+        }
+    }
+
+    #[test]
+    fn b85decode_rejects_invalid() {
+        // Space is not within alphabet
+        assert_eq!(b85decode("VPa s"), None);
+        // The word overflows u32
+        assert_eq!(b85decode("~~~~~"), None);
+    }
+
+    #[test]
+    fn encrypter_rejects_bad_key() {
+        let new = |key: &str| Encrypter::new("secret".into(), key).is_err();
+        assert!(new(&b85encode(&[1; 32])));
+        assert!(new(&format!("2:{}", b85encode(&[1; 32]))));
+        assert!(new(&kitty_key(&[1; 31])));
+        assert!(new(&kitty_key(&[1; 33])));
+        assert!(new("1:VPa s"));
+        assert!(!new(&kitty_key(&[1; 32])));
+    }
+
+    #[test]
+    fn encrypted_command_is_decrypted_by_kitty() {
+        let kitty = EphemeralSecret::random_from_rng(OsRng);
+        let kitty_public = PublicKey::from(&kitty);
+        let encrypter = Encrypter::new(
+            "secret".into(),
+            &kitty_key(kitty_public.as_bytes()),
+        )
+        .unwrap();
+        let cmd = json!({"cmd": "ls", "version": [0, 26, 0]});
+        let envelope = encrypter.encrypt(cmd).unwrap();
+        assert_eq!(envelope["version"], json!([0, 26, 0]));
+
+        let field =
+            |name: &str| b85decode(envelope[name].as_str().unwrap()).unwrap();
+        let pubkey: [u8; 32] = field("pubkey").try_into().unwrap();
+        let shared = kitty.diffie_hellman(&PublicKey::from(pubkey));
+        let secret: [u8; 32] = Sha256::digest(shared.as_bytes()).into();
+        let mut data = field("encrypted");
+        Aes256Gcm::new(&secret.into())
+            .decrypt_in_place_detached(
+                Nonce::from_slice(&field("iv")),
+                b"",
+                &mut data,
+                Tag::from_slice(&field("tag")),
+            )
+            .unwrap();
+        let cmd: Value = serde_json::from_slice(&data).unwrap();
+        assert_eq!(cmd["cmd"], "ls");
+        assert_eq!(cmd["password"], "secret");
+        assert!(cmd["timestamp"].as_u64().is_some());
+    }
+}
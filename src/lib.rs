@@ -10,18 +10,50 @@
 
 use clap::Subcommand;
 pub use clap::{Parser, ValueEnum};
-use niri_ipc::{Request, Response, socket::Socket};
-use regex;
-use std::ffi::OsString;
-use std::fs::{read_link, File};
-use std::io::BufRead;
-use std::str;
-use std::str::FromStr;
+use config::Config;
+pub use editor::EditorFrontend;
+use env_diff::EnvDiff;
+pub use env_policy::EnvPolicy;
+pub use error::{Error, Result};
+pub use multiplexer::TmuxPane;
+use niri_ipc::{socket::Socket, Request, Response};
+use placement::Watcher;
+pub use placement::{ColumnPlacement, Placement};
+pub use provider::{ContextProvider, ProviderRegistry};
+use provider::{
+    KittyContext, KittyProvider, NeovideProvider, NvimContext,
+    TerminalProvider, WeztermProvider,
+};
+pub use raise::{Raise, RaiseMatcher, RaiseMode};
+pub use selector::{Side, WindowSelector};
+use serde::Deserialize;
+pub use ssh::SshTarget;
 use std::{
-    collections::HashMap, io, os::unix::process::CommandExt, path::PathBuf,
+    collections::HashMap, ffi::OsString, io, os::unix::process::CommandExt,
+    path::PathBuf,
 };
 
+mod config;
+mod doctor;
+mod dry_run;
+mod editor;
+mod env_diff;
+mod env_format;
+mod env_policy;
+mod error;
 mod kitty;
+mod mru;
+mod multiplexer;
+mod nvim;
+mod placement;
+mod process;
+pub mod provider;
+mod quote;
+mod raise;
+mod selector;
+mod ssh;
+mod stream;
+mod wezterm;
 
 /// Top-level arguments structure
 #[derive(Parser, Debug)]
@@ -40,280 +72,878 @@ pub struct Launcher {
     #[arg(short, long, help = "Path to niri socket")]
     path: Option<PathBuf>,
 
+    /// Optional path to configuration file
+    ///
+    /// By default `$XDG_CONFIG_HOME/niri-launcher/config.toml` is used if
+    /// exists
+    #[arg(short, long)]
+    config: Option<PathBuf>,
+
     /// Optional template of kitty socket
     ///
     /// Will accept environment variables in view `${ENV}` and `{pid}` construction
-    /// which will be replaced with pid of target kitty process
-    #[arg(short, long, default_value = "${XDG_RUNTIME_DIR}/kitty-{pid}")]
-    kitty_socket: String,
+    /// which will be replaced with pid of target kitty process. Accepts kitty
+    /// `listen_on` syntax: `unix:/path`, `unix:@abstract` and
+    /// `tcp:host:port` as well as plain path. Overrides
+    /// `kitty.socket` from configuration file which defaults to
+    /// `${XDG_RUNTIME_DIR}/kitty-{pid}`
+    #[arg(short, long)]
+    kitty_socket: Option<String>,
+
+    /// Where to take context of kitty window from
+    ///
+    /// Overrides `kitty.context` from configuration file which defaults to
+    /// `process`
+    #[arg(long)]
+    kitty_context: Option<KittyContext>,
+
+    /// Which directory of neovim within neovide to use as cwd
+    ///
+    /// Overrides `neovide.context` from configuration file which defaults to
+    /// `cwd`
+    #[arg(long)]
+    nvim_context: Option<NvimContext>,
 
     /// Whenever to launch tool regardless to current focused window
     ///
-    /// Launching tool will be run with default cwd withing default environment
+    /// Launching tool will be run with default cwd withing default environment.
+    /// Niri is not required in this mode.
     #[arg(short, long, default_value = "false")]
     fresh: bool,
 
-    /// Optional niri window id to base window
+    /// Print the command instead of executing it
+    ///
+    /// Prints program, arguments, cwd and the environment variables which
+    /// differ from the launcher's own ones.
+    #[arg(
+        long,
+        value_enum,
+        value_name = "FORMAT",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "shell"
+    )]
+    dry_run: Option<DryRunFormat>,
+
+    /// Pass only environment which differs from baseline
+    ///
+    /// Overrides `env.diff` from configuration file which defaults to `off`
+    #[arg(long)]
+    env_diff: Option<EnvDiff>,
+
+    /// Optional selector of base window
     ///
-    /// By default this uses focused window
+    /// Accepts niri window id, `mru:N` for the N-th window of focus history
+    /// recorded by `daemon`, `app:<regex>`, `title:/<regex>/`, `pid:N`,
+    /// `workspace:<name>` for window with usable context on workspace,
+    /// `column-neighbor:left|right` and `pick` to choose window with mouse.
+    /// By default this uses focused window and, if it has no usable context,
+    /// the most recently focused window with one.
     #[arg(short, long)]
-    window: Option<u64>,
+    window: Option<WindowSelector>,
+
+    /// Placement of new window within niri
+    #[command(flatten)]
+    placement: Placement,
+
+    /// Focusing of existing window instead of launch
+    #[command(flatten)]
+    raise: Raise,
+
+    /// Settings loaded from configuration file and overridden by arguments
+    #[arg(skip)]
+    settings: Config,
+
+    /// Providers of launching data
+    #[arg(skip)]
+    providers: ProviderRegistry,
 }
 
 /// The list of supported commands
 #[derive(Subcommand, Debug, Clone)]
 #[command(about, long_about)]
 pub enum Command {
-    /// Check niri availability.
+    /// Diagnose launcher environment.
     ///
-    /// Exits with success if niri is available and panics if niri is
-    /// unavailable.
-    #[command(about, long_about)]
-    Test,
+    /// Checks configuration file, availability of kitty and neovide binaries,
+    /// niri connection and version, the base window with its provider and
+    /// the kitty socket of base window. Prints report and fails with the
+    /// error of the first failed check.
+    #[command(about, long_about, visible_alias = "test")]
+    Doctor {
+        /// Format of report
+        #[arg(long, value_enum, default_value_t = ReportFormat::Table)]
+        format: ReportFormat,
+    },
 
     /// Run new kitty instance.
     ///
     /// If current focused window have usable environment data (e.g. another kitty
     /// window) - the newly running window will inherit this environment (e.g. cwd).
     #[command(about, long_about)]
-    Kitty,
+    Kitty {
+        /// Launch new window inside the focused kitty instance
+        ///
+        /// Falls back to new kitty process if base window is not kitty or its
+        /// socket is unavailable.
+        #[arg(long)]
+        into: Option<KittyInto>,
+
+        /// Where to place new kitty window within the tab
+        #[arg(long, requires = "into")]
+        location: Option<KittyLocation>,
+
+        /// Open new tmux window or pane when context is taken from tmux
+        ///
+        /// Only cwd is inherited in this case. Takes precedence over
+        /// `--into`. Falls back to other ways if the foreground process of
+        /// base window is not tmux client.
+        #[arg(long)]
+        tmux: Option<TmuxInto>,
+    },
+
+    /// Run new WezTerm instance.
+    ///
+    /// If current focused window have usable environment data (e.g. another
+    /// terminal window) - the newly running window will inherit this
+    /// environment (e.g. cwd).
+    #[command(about, long_about)]
+    Wezterm {
+        /// Spawn new tab or window inside the focused WezTerm instance
+        ///
+        /// Only cwd is inherited in this case. Falls back to new WezTerm
+        /// process if base window is not WezTerm or its CLI fails.
+        #[arg(long)]
+        into: Option<WeztermInto>,
+    },
 
     /// Print env for launching command.
     ///
     /// If current focused window have usable environment data (e.g. kitty
-    /// window) - this will print environment to use with new window. Usable for development
-    /// purposes.
+    /// window) - this will print environment to use with new window along
+    /// with cwd and the provider the data was taken with. Usable for
+    /// development purposes and for other tools to `eval` or parse.
     #[command(about, long_about)]
-    Env,
+    Env {
+        /// Output format
+        #[arg(long, value_enum, default_value_t = EnvFormat::Sh)]
+        format: EnvFormat,
+    },
 
-    /// Run new vim instance.
+    /// Run new vim instance or open files within existing one.
     ///
     /// If current focused window have usable environment data (e.g. kitty
     /// window) - the newly running window will inherit this environment (e.g. cwd).
+    /// When neovim server of the project (the git root of the first file or
+    /// of cwd) is already running the files are opened within it and its
+    /// window is focused. New neovide listens on predictable socket of the
+    /// project.
     #[command(about, long_about)]
-    Vim,
+    Vim {
+        /// The frontend to run neovim with
+        ///
+        /// Overrides `editor.frontend` from configuration file which
+        /// defaults to `neovide`
+        #[arg(long)]
+        frontend: Option<EditorFrontend>,
+
+        /// Files to open
+        files: Vec<PathBuf>,
+    },
+
+    /// Record history of focused windows.
+    ///
+    /// Follows niri event stream and stores the order of focused windows
+    /// for other launcher instances. With running daemon launches fall back
+    /// to the most recently focused window with usable context and
+    /// `--window mru:N` is available.
+    #[command(about, long_about)]
+    Daemon,
+
+    /// Run arbitrary command.
+    ///
+    /// If current focused window have usable environment data (e.g. kitty
+    /// window) - the command will inherit this environment (e.g. cwd). Use
+    /// `run -- <cmd> [args...]` to pass arguments starting with dash.
+    #[command(about, long_about)]
+    Run {
+        /// The program to run followed by its arguments
+        #[arg(
+            required = true,
+            trailing_var_arg = true,
+            allow_hyphen_values = true
+        )]
+        command: Vec<OsString>,
+    },
+}
+
+/// Output format of diagnostic report
+#[derive(ValueEnum, Debug, Clone, Copy)]
+pub enum ReportFormat {
+    /// Human readable table
+    Table,
+    /// JSON array of checks
+    Json,
 }
 
-#[derive(Default)]
-struct LaunchingData {
+/// Output format of `env` command
+#[derive(ValueEnum, Debug, Clone, Copy)]
+pub enum EnvFormat {
+    /// POSIX shell `cd` and `export` commands
+    Sh,
+    /// Fish shell `cd` and `set -gx` commands
+    Fish,
+    /// JSON object with `provider`, `cwd` and `env` fields
+    Json,
+    /// Systemd environment file with cwd as comment
+    Systemd,
+    /// NUL separated records: cwd, provider, `NAME=VALUE` pairs and names of
+    /// variables to unset
+    Null,
+}
+
+/// Output format of dry run
+#[derive(ValueEnum, Debug, Clone, Copy)]
+pub enum DryRunFormat {
+    /// Shell command line
+    Shell,
+    /// JSON object
+    Json,
+}
+
+/// The kind of window to open inside existing kitty instance
+#[derive(ValueEnum, Deserialize, Debug, Clone, Copy, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum KittyInto {
+    /// New kitty window within current tab
+    Window,
+    /// New tab within current OS window
+    #[default]
+    Tab,
+    /// New OS window of the same kitty instance
+    OsWindow,
+    /// Overlay over current kitty window
+    Overlay,
+}
+
+/// The way to open new shell within tmux session
+#[derive(ValueEnum, Debug, Clone, Copy)]
+pub enum TmuxInto {
+    /// New window after the current one
+    Window,
+    /// Split of the active pane
+    Pane,
+}
+
+/// The kind of window to spawn inside existing WezTerm instance
+#[derive(ValueEnum, Debug, Clone, Copy)]
+pub enum WeztermInto {
+    /// New tab within window of active pane
+    Tab,
+    /// New window of the same WezTerm instance
+    Window,
+}
+
+/// The location of new kitty window within tab layout
+#[derive(ValueEnum, Debug, Clone, Copy)]
+pub enum KittyLocation {
+    /// After current window
+    After,
+    /// Before current window
+    Before,
+    /// Default location of layout
+    Default,
+    /// First window of the tab
+    First,
+    /// Horizontal split of current window
+    Hsplit,
+    /// Last window of the tab
+    Last,
+    /// Neighbor of current window
+    Neighbor,
+    /// Split of current window along its longer side
+    Split,
+    /// Vertical split of current window
+    Vsplit,
+}
+
+/// The context to launch new process within
+#[derive(Default, Debug, Clone)]
+pub struct LaunchingData {
+    /// Environment variables of new process
     pub env: HashMap<String, String>,
+    /// Working directory of new process
     pub cwd: Option<String>,
+    /// Name of provider the data was taken with
+    pub provider: Option<String>,
+    /// Environment variables to remove from new process
+    pub unset_env: Vec<String>,
+    /// The tmux pane the data was taken from
+    pub tmux: Option<TmuxPane>,
+    /// The ssh connection running within source
+    pub ssh: Option<SshTarget>,
+    /// Whether `env` holds the whole environment of source
+    ///
+    /// Only in this case the variables absent in `env` are known to be unset
+    /// within source.
+    pub env_complete: bool,
 }
 
 impl Launcher {
     /// Run chosen subcommand
-    pub fn run(self) -> io::Result<()> {
-        let mut socket = if let Some(path) = self.path.as_ref() {
-            Socket::connect_to(path)?
+    pub fn run(mut self) -> Result<()> {
+        if let Command::Doctor { format } = self.command {
+            return self.run_doctor(format);
+        }
+        self.load_settings()?;
+        let runner: Runner = match self.command {
+            Command::Doctor { .. } => unreachable!("doctor is run separately"),
+            Command::Daemon => return self.run_daemon(),
+            Command::Kitty { .. } => Self::run_kitty,
+            Command::Wezterm { .. } => Self::run_wezterm,
+            Command::Env { .. } => Self::print_env,
+            Command::Vim { .. } => Self::run_vim,
+            Command::Run { .. } => Self::run_command,
+        };
+
+        // Fresh launches do not need niri at all
+        let mut socket = if self.fresh {
+            None
         } else {
-            Socket::connect()?
+            Some(self.connect_niri()?)
+        };
+        let window = match socket.as_mut() {
+            Some(socket) => self.get_base_window(socket)?,
+            None => None,
         };
-        let runner: fn(LaunchingData) -> io::Result<()> = match self.command {
-            Command::Test => Self::run_test,
-            Command::Kitty => Self::run_kitty,
-            Command::Env => Self::print_env,
-            Command::Vim => Self::run_vim,
+        let data = self.get_launching_data(socket.as_mut(), window.as_ref())?;
+        let policies = self
+            .settings
+            .env
+            .policies(data.provider.as_deref(), self.command.name());
+        let data = policies.iter().fold(data, LaunchingData::filter_env);
+        let data = match self.settings.env.diff.baseline()? {
+            Some(baseline) => data.diff_env(&baseline, |name| {
+                policies.iter().all(|policy| policy.allows(name))
+            }),
+            None => data,
         };
+        if !matches!(self.command, Command::Env { .. }) && self.raise(&data)? {
+            return Ok(());
+        }
+        runner(&self, window.as_ref(), data)
+    }
+
+    fn connect_niri(&self) -> Result<Socket> {
+        if let Some(path) = self.settings.niri_socket.as_ref() {
+            Socket::connect_to(path)
+        } else {
+            Socket::connect()
+        }
+        .map_err(Error::NiriConnection)
+    }
 
-        runner(self.get_launching_data(&mut socket))
+    fn load_settings(&mut self) -> Result<()> {
+        let settings = Config::load(self.config.as_ref())?;
+        self.apply_settings(settings)
     }
 
-    fn get_socket(&self, pid: i32) -> io::Result<kitty::KittySocket> {
-        let pidre = regex::Regex::new(r"\{pid\}").unwrap();
-        let envre = regex::Regex::new(r"\$\{([^\{\}\s]*)\}").unwrap();
+    /// Override settings with arguments and register builtin providers
+    fn apply_settings(&mut self, mut settings: Config) -> Result<()> {
+        if let Some(path) = self.path.take() {
+            settings.niri_socket = Some(path);
+        }
+        if let Some(kitty_socket) = self.kitty_socket.take() {
+            settings.kitty.socket = kitty_socket;
+        }
+        if let Some(kitty_context) = self.kitty_context.take() {
+            settings.kitty.context = kitty_context;
+        }
+        if let Some(nvim_context) = self.nvim_context.take() {
+            settings.neovide.context = nvim_context;
+        }
+        if let Some(env_diff) = self.env_diff.take() {
+            settings.env.diff = env_diff;
+        }
+        self.settings = settings;
 
-        let path =
-            envre.replace_all(&self.kitty_socket, |caps: &regex::Captures| {
-                let var = std::env::var_os(&caps[1].to_string())
-                    .unwrap_or(OsString::from(""));
-                String::from(var.to_str().unwrap())
-            });
+        let mut builtin = ProviderRegistry::new();
+        builtin.register(
+            &self.settings.kitty.app_id,
+            self.kitty_provider()?
+                .with_context(self.settings.kitty.context)
+                .with_cwd_user_var(self.settings.kitty.cwd_user_var.clone()),
+        )?;
+        builtin.register(
+            &self.settings.neovide.app_id,
+            NeovideProvider::new().with_context(self.settings.neovide.context),
+        )?;
+        builtin
+            .register(&self.settings.wezterm.app_id, self.wezterm_provider())?;
+        for app_id in &self.settings.terminal.app_ids {
+            builtin.register(app_id, TerminalProvider::new())?;
+        }
+        self.providers.append(&mut builtin);
+        Ok(())
+    }
 
-        let path = pidre.replace_all(&path, format!("{pid}"));
+    fn kitty_provider(&self) -> Result<KittyProvider> {
+        Ok(KittyProvider::new(&self.settings.kitty.socket)
+            .with_password(self.settings.kitty.password()?))
+    }
 
-        kitty::KittySocket::connect(PathBuf::from(path.to_string()))
+    fn wezterm_provider(&self) -> WeztermProvider {
+        WeztermProvider::new(
+            &self.settings.wezterm.command,
+            &self.settings.wezterm.socket,
+        )
     }
 
-    fn get_launching_data_no_default(
+    /// Register additional context provider
+    ///
+    /// The providers registered with this function take precedence over
+    /// builtin ones. The `app_id` is regular expression which should match
+    /// the whole app_id of base window.
+    pub fn register_provider<P>(
+        &mut self,
+        app_id: &str,
+        provider: P,
+    ) -> Result<()>
+    where
+        P: ContextProvider + 'static,
+    {
+        self.providers.register(app_id, provider)
+    }
+
+    /// Get launching data from base window or from focus history
+    ///
+    /// The history is used only when base window is not chosen explicitly.
+    fn get_launching_data(
         &self,
-        socket: &mut Socket,
-    ) -> io::Result<LaunchingData> {
-        let window = self.get_base_window(socket).ok_or(io::Error::new(
-            io::ErrorKind::NotFound,
-            "No focused niri window",
-        ))?;
-        let class = window.app_id.ok_or(io::Error::new(
-            io::ErrorKind::NotFound,
-            "Focused niri window does not have class",
-        ))?;
-        if class == "kitty" {
-            self.get_launching_data_from_kitty(window.pid)
-        } else if class == "neovide" {
-            self.get_launching_data_from_vim(window.pid)
-        } else {
-            Err(io::Error::new(
-                io::ErrorKind::Unsupported,
-                format!("Can not get launching data from {class}"),
-            ))
+        socket: Option<&mut Socket>,
+        window: Option<&niri_ipc::Window>,
+    ) -> Result<LaunchingData> {
+        let data = window
+            .and_then(|window| self.providers.get_launching_data(window).ok());
+        let (None, Some(socket), None) = (data.as_ref(), socket, &self.window)
+        else {
+            return Ok(data.unwrap_or_default());
+        };
+        let history = mru::History::load();
+        if history.ids().is_empty() {
+            return Ok(LaunchingData::default());
         }
+        let windows = niri_windows(socket)?;
+        Ok(history
+            .ids()
+            .iter()
+            .filter(|id| window.is_none_or(|window| window.id != **id))
+            .filter_map(|id| windows.iter().find(|window| window.id == *id))
+            .find_map(|window| self.providers.get_launching_data(window).ok())
+            .unwrap_or_default())
     }
 
-    fn get_launching_data(&self, socket: &mut Socket) -> LaunchingData {
-        if self.fresh {
-            LaunchingData::default()
-        } else {
-            self.get_launching_data_no_default(socket)
-                .unwrap_or(LaunchingData::default())
-        }
+    fn is_kitty(&self, window: &niri_ipc::Window) -> Result<bool> {
+        let app_id = window.app_id.as_deref().unwrap_or_default();
+        Ok(provider::app_id_pattern(&self.settings.kitty.app_id)?
+            .is_match(app_id))
     }
 
-    fn get_launching_data_from_kitty(
+    fn run_kitty(
         &self,
-        pid: Option<i32>,
-    ) -> io::Result<LaunchingData> {
-        let pid = pid.ok_or(io::Error::new(
-            io::ErrorKind::NotFound,
-            "Focused niri window does not have pid",
-        ))?;
-        let mut socket = self.get_socket(pid)?;
-        let r = kitty::Command::Ls(kitty::Ls::default());
-        let r = socket.request(r)?;
-        let windows: Vec<kitty::OsWindow> = serde_json::from_value(r).unwrap();
-        let window = Self::find_kitty_focused_window(windows).ok_or(
-            io::Error::new(io::ErrorKind::NotFound, "No focused kitty window"),
-        )?;
-        Ok(LaunchingData::default()
-            .maybe_cwd(window.cwd.to_str())
-            .set_envs(window.env.into_iter()))
+        window: Option<&niri_ipc::Window>,
+        data: LaunchingData,
+    ) -> Result<()> {
+        // Connect to the same host instead of running local shell
+        let ssh = data
+            .ssh
+            .as_ref()
+            .map(SshTarget::command)
+            .unwrap_or_default();
+        if let (
+            Command::Kitty {
+                tmux: Some(into), ..
+            },
+            Some(pane),
+        ) = (&self.command, data.tmux.as_ref())
+        {
+            if self.launch_into_tmux(pane, *into, &data).is_ok() {
+                return Ok(());
+            }
+        }
+        if let (
+            Command::Kitty {
+                into: Some(into),
+                location,
+                ..
+            },
+            Some(window),
+        ) = (&self.command, window)
+        {
+            if self
+                .launch_into_kitty(window, *into, *location, &data, ssh.clone())
+                .is_ok()
+            {
+                return Ok(());
+            }
+        }
+
+        let mut proc = self.kitty_command(&self.settings.kitty.command, data);
+        proc.args(ssh);
+        self.exec(window, proc)
     }
 
-    fn get_launching_data_from_vim(
+    fn launch_into_tmux(
         &self,
-        pid: Option<i32>,
-    ) -> io::Result<LaunchingData> {
-        let pid = pid.ok_or(io::Error::new(
-            io::ErrorKind::NotFound,
-            "Focused niri window does not have pid",
-        ))?;
-        let environ = File::open(format!("/proc/{pid}/environ"))?;
-        let lines = io::BufReader::new(environ).split(0x0);
-        let launching_data =
-            lines.fold(LaunchingData::default(), |launching_data, line| {
-                if let Ok(line) = line {
-                    if let Ok(line) = str::from_utf8(&line) {
-                        if let Some((k, v)) = line.split_once("=") {
-                            launching_data.add_env(k, v)
-                        } else {
-                            launching_data
-                        }
-                    } else {
-                        launching_data
-                    }
-                } else {
-                    launching_data
-                }
-            });
-
-        let Ok(cwd) = PathBuf::from_str(&format!("/proc/{pid}/cwd"));
-        let cwd = read_link(&cwd)
-            .ok()
-            .map(|cwd| String::from(cwd.to_str().unwrap()));
-        Ok(launching_data.maybe_cwd(cwd))
-    }
-
-    fn run_test(_: LaunchingData) -> io::Result<()> {
+        pane: &TmuxPane,
+        into: TmuxInto,
+        data: &LaunchingData,
+    ) -> Result<()> {
+        let mut proc = match into {
+            TmuxInto::Window => pane.command(["new-window", "-a"]),
+            TmuxInto::Pane => pane.command(["split-window"]),
+        };
+        proc.arg("-t").arg(&pane.pane);
+        if let Some(workdir) = data.cwd.as_ref() {
+            proc.arg("-c").arg(workdir);
+        }
+        if let Some(format) = self.dry_run {
+            dry_run::print(&proc, format);
+            return Ok(());
+        }
+        let output = proc.output().map_err(Error::ExecFailed)?;
+        if !output.status.success() {
+            return Err(Error::ExecFailed(io::Error::other(
+                String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            )));
+        }
         Ok(())
     }
 
-    fn run_kitty(data: LaunchingData) -> io::Result<()> {
-        let mut proc = std::process::Command::new("kitty");
+    /// Get command running new kitty binary `command` within context of `data`
+    fn kitty_command(
+        &self,
+        command: &str,
+        data: LaunchingData,
+    ) -> std::process::Command {
+        let mut proc = std::process::Command::new(command);
 
         data.env.into_iter().fold(&mut proc, |proc, (name, val)| {
             proc.arg("-o").arg(format!("env={name}={val}"))
         });
-
-        data.cwd.map(|workdir| {
-            proc.arg("-d").arg(format!("{}", workdir));
+        // Kitty removes variable specified by name only
+        data.unset_env.into_iter().fold(&mut proc, |proc, name| {
+            proc.arg("-o").arg(format!("env={name}"))
         });
 
-        Err(proc.exec())
+        if let Some(workdir) = data.cwd {
+            proc.arg("-d").arg(workdir);
+        }
+        proc
     }
 
-    fn print_env(launching_data: LaunchingData) -> io::Result<()> {
-        for (name, val) in launching_data.env {
-            println!("{name}=\"{val}\"");
+    fn launch_into_kitty(
+        &self,
+        window: &niri_ipc::Window,
+        into: KittyInto,
+        location: Option<KittyLocation>,
+        data: &LaunchingData,
+        args: Vec<String>,
+    ) -> Result<()> {
+        if !self.is_kitty(window)? {
+            return Err(Error::ProviderUnsupported(
+                "Base window is not kitty".into(),
+            ));
         }
-        Ok(())
+        let launch = kitty::Launch {
+            args,
+            cwd: data.cwd.as_ref().map(PathBuf::from),
+            env: Some(
+                data.env
+                    .iter()
+                    .map(|(name, val)| format!("{name}={val}"))
+                    .chain(data.unset_env.iter().cloned())
+                    .collect(),
+            ),
+            launch_type: Some(into.into()),
+            location: location.map(kitty::LaunchLocation::from),
+            ..Default::default()
+        };
+        let pid = provider::window_pid(window)?;
+        let mut found = self.kitty_provider()?.discover(pid)?;
+        if let Some(format) = self.dry_run {
+            let to = if found.address.starts_with("unix:")
+                || found.address.starts_with("tcp:")
+            {
+                found.address
+            } else {
+                format!("unix:{}", found.address)
+            };
+            let mut proc =
+                std::process::Command::new(&self.settings.kitty.command);
+            proc.args(["@", "--to", &to, "launch"])
+                .args(launch.to_args());
+            dry_run::print(&proc, format);
+            return Ok(());
+        }
+        // Only OS windows of kitty are separate niri windows
+        let watcher = match into {
+            KittyInto::OsWindow if self.placement.is_requested() => {
+                Some(Watcher::new(self.connect_niri()?)?)
+            }
+            _ => None,
+        };
+        found
+            .socket
+            .request(kitty::Command::Launch(Box::new(launch)))?;
+        match watcher {
+            Some(watcher) => self.place(&watcher, pid, Some(window)),
+            None => Ok(()),
+        }
+    }
+
+    fn is_wezterm(&self, window: &niri_ipc::Window) -> Result<bool> {
+        let app_id = window.app_id.as_deref().unwrap_or_default();
+        Ok(provider::app_id_pattern(&self.settings.wezterm.app_id)?
+            .is_match(app_id))
     }
 
-    fn run_vim(data: LaunchingData) -> io::Result<()> {
-        let mut proc = std::process::Command::new("neovide");
+    fn run_wezterm(
+        &self,
+        window: Option<&niri_ipc::Window>,
+        data: LaunchingData,
+    ) -> Result<()> {
+        if let (Command::Wezterm { into: Some(into) }, Some(window)) =
+            (&self.command, window)
+        {
+            if self.spawn_into_wezterm(window, *into, &data).is_ok() {
+                return Ok(());
+            }
+        }
+
+        let mut proc =
+            std::process::Command::new(&self.settings.wezterm.command);
+        proc.arg("start");
+        if let Some(workdir) = data.cwd.as_ref() {
+            proc.arg("--cwd").arg(workdir);
+        }
+        self.exec_within(window, proc, data)
+    }
 
+    fn spawn_into_wezterm(
+        &self,
+        window: &niri_ipc::Window,
+        into: WeztermInto,
+        data: &LaunchingData,
+    ) -> Result<()> {
+        if !self.is_wezterm(window)? {
+            return Err(Error::ProviderUnsupported(
+                "Base window is not WezTerm".into(),
+            ));
+        }
+        let pid = provider::window_pid(window)?;
+        let cli = self.wezterm_provider().cli(pid);
+        let pane = WeztermProvider::find_active_pane(&cli, window)?;
+        let mut args =
+            vec!["spawn".into(), "--pane-id".into(), pane.pane_id.to_string()];
+        if let WeztermInto::Window = into {
+            args.push("--new-window".into());
+        }
+        if let Some(workdir) = data.cwd.as_ref() {
+            args.extend(["--cwd".into(), workdir.clone()]);
+        }
+        let proc = cli.command(args);
+        if let Some(format) = self.dry_run {
+            dry_run::print(&proc, format);
+            return Ok(());
+        }
+        // Windows of the same WezTerm instance share its pid
+        let watcher = match into {
+            WeztermInto::Window if self.placement.is_requested() => {
+                Some(Watcher::new(self.connect_niri()?)?)
+            }
+            _ => None,
+        };
+        wezterm::output(proc)?;
+        match watcher {
+            Some(watcher) => self.place(&watcher, pid, Some(window)),
+            None => Ok(()),
+        }
+    }
+
+    fn print_env(
+        &self,
+        _: Option<&niri_ipc::Window>,
+        launching_data: LaunchingData,
+    ) -> Result<()> {
+        let Command::Env { format } = self.command else {
+            unreachable!("print_env is used only for Command::Env");
+        };
+        Ok(env_format::print(&launching_data, format)?)
+    }
+
+    fn run_command(
+        &self,
+        window: Option<&niri_ipc::Window>,
+        data: LaunchingData,
+    ) -> Result<()> {
+        let Command::Run { command } = &self.command else {
+            unreachable!("run_command is used only for Command::Run");
+        };
+        let mut proc = std::process::Command::new(&command[0]);
+        proc.args(&command[1..]);
+
+        self.exec_within(window, proc, data)
+    }
+
+    fn exec_within(
+        &self,
+        window: Option<&niri_ipc::Window>,
+        mut proc: std::process::Command,
+        data: LaunchingData,
+    ) -> Result<()> {
         data.env
             .into_iter()
             .fold(&mut proc, |proc, (name, val)| proc.env(name, val));
+        data.unset_env
+            .into_iter()
+            .fold(&mut proc, |proc, name| proc.env_remove(name));
 
-        data.cwd.map(|workdir| {
+        if let Some(workdir) = data.cwd {
             proc.current_dir(workdir);
-        });
+        }
 
-        Err(proc.exec())
-    }
-
-    fn find_kitty_focused_window(
-        windows: Vec<kitty::OsWindow>,
-    ) -> Option<kitty::Window> {
-        for window in windows {
-            if window.is_focused {
-                for tab in window.tabs {
-                    if tab.is_focused {
-                        for window in tab.windows {
-                            if window.is_focused {
-                                return Some(window);
-                            }
-                        }
-                    }
-                }
-            }
+        self.exec(window, proc)
+    }
+
+    /// Replace current process with `proc` or print it in dry run mode
+    ///
+    /// When [placement](Placement) is requested the `proc` is spawned
+    /// instead and its window is placed relative to base `window`.
+    fn exec(
+        &self,
+        window: Option<&niri_ipc::Window>,
+        mut proc: std::process::Command,
+    ) -> Result<()> {
+        if let Some(format) = self.dry_run {
+            dry_run::print(&proc, format);
+            return Ok(());
         }
-        None
+        if !self.placement.is_requested() {
+            return Err(Error::ExecFailed(proc.exec()));
+        }
+        // Subscribe before spawn to not miss the window
+        let watcher = Watcher::new(self.connect_niri()?)?;
+        let child = proc.spawn().map_err(Error::ExecFailed)?;
+        self.place(&watcher, child.id() as i32, window)
+    }
+
+    /// Wait for new window of process `pid` and place it
+    fn place(
+        &self,
+        watcher: &Watcher,
+        pid: i32,
+        base: Option<&niri_ipc::Window>,
+    ) -> Result<()> {
+        let window = watcher.wait_for(pid).ok_or(Error::NiriProtocol(
+            "No window of launched process appeared".into(),
+        ))?;
+        self.placement
+            .apply(&mut self.connect_niri()?, &window, base)
     }
 
     fn get_base_window(
         &self,
         socket: &mut Socket,
-    ) -> Option<niri_ipc::Window> {
-        if let Some(id) = self.window {
-            if let Response::Windows(windows) =
-                socket.send(Request::Windows).unwrap().unwrap()
-            {
-                let mut res = None;
-                for window in windows.into_iter() {
-                    if window.id == id {
-                        res = Some(window);
-                        break;
-                    }
-                }
-                res
-            } else {
-                panic!("Unexpected response to Windows")
-            }
-        } else if let Response::FocusedWindow(window) =
-            socket.send(Request::FocusedWindow).unwrap().unwrap()
-        {
-            window
-        } else {
-            panic!("Unexpected response to FocusedWindow")
+    ) -> Result<Option<niri_ipc::Window>> {
+        match self.window.as_ref() {
+            Some(selector) => selector.select(socket, &self.providers),
+            None => niri_focused_window(socket),
+        }
+    }
+}
+
+/// Send request to niri and wait for successful response
+fn niri_request(socket: &mut Socket, request: Request) -> Result<Response> {
+    socket
+        .send(request)
+        .map_err(Error::NiriConnection)?
+        .map_err(Error::NiriProtocol)
+}
+
+/// Get focused niri window
+fn niri_focused_window(
+    socket: &mut Socket,
+) -> Result<Option<niri_ipc::Window>> {
+    match niri_request(socket, Request::FocusedWindow)? {
+        Response::FocusedWindow(window) => Ok(window),
+        _ => Err(Error::NiriProtocol(
+            "Unexpected response to FocusedWindow".into(),
+        )),
+    }
+}
+
+/// Get all niri windows
+fn niri_windows(socket: &mut Socket) -> Result<Vec<niri_ipc::Window>> {
+    match niri_request(socket, Request::Windows)? {
+        Response::Windows(windows) => Ok(windows),
+        _ => Err(Error::NiriProtocol("Unexpected response to Windows".into())),
+    }
+}
+
+impl Command {
+    /// Name of subcommand as used on command line
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Doctor { .. } => "doctor",
+            Self::Kitty { .. } => "kitty",
+            Self::Wezterm { .. } => "wezterm",
+            Self::Env { .. } => "env",
+            Self::Vim { .. } => "vim",
+            Self::Daemon => "daemon",
+            Self::Run { .. } => "run",
+        }
+    }
+}
+
+type Runner =
+    fn(&Launcher, Option<&niri_ipc::Window>, LaunchingData) -> Result<()>;
+
+impl From<KittyInto> for kitty::LaunchType {
+    fn from(value: KittyInto) -> Self {
+        match value {
+            KittyInto::Window => Self::Window,
+            KittyInto::Tab => Self::Tab,
+            KittyInto::OsWindow => Self::OsWindow,
+            KittyInto::Overlay => Self::Overlay,
+        }
+    }
+}
+
+impl From<KittyLocation> for kitty::LaunchLocation {
+    fn from(value: KittyLocation) -> Self {
+        match value {
+            KittyLocation::After => Self::After,
+            KittyLocation::Before => Self::Before,
+            KittyLocation::Default => Self::Default,
+            KittyLocation::First => Self::First,
+            KittyLocation::Hsplit => Self::Hsplit,
+            KittyLocation::Last => Self::Last,
+            KittyLocation::Neighbor => Self::Neighbor,
+            KittyLocation::Split => Self::Split,
+            KittyLocation::Vsplit => Self::Vsplit,
         }
     }
 }
 
 impl LaunchingData {
+    /// Reset working directory to default one
     pub fn clear_cwd(mut self) -> Self {
         self.cwd = None;
         self
     }
 
+    /// Set working directory
     pub fn set_cwd<S>(mut self, cwd: S) -> Self
     where
         S: Into<String>,
@@ -322,6 +952,7 @@ impl LaunchingData {
         self
     }
 
+    /// Set working directory or reset it to default one if [None]
     pub fn maybe_cwd<S>(mut self, cwd: Option<S>) -> Self
     where
         S: Into<String>,
@@ -330,11 +961,51 @@ impl LaunchingData {
         self
     }
 
+    /// Drop environment variables which are not allowed by `policy`
+    pub fn filter_env(mut self, policy: &EnvPolicy) -> Self {
+        self.env.retain(|name, _| policy.allows(name));
+        self
+    }
+
+    /// Mark `env` as the whole environment of source
+    pub fn complete_env(mut self) -> Self {
+        self.env_complete = true;
+        self
+    }
+
+    /// Keep only variables which differ from `baseline`
+    ///
+    /// If `env` is [complete](Self::complete_env) the variables of baseline
+    /// absent in `env` and accepted by `unsettable` are added to `unset_env`.
+    pub fn diff_env<F>(
+        mut self,
+        baseline: &HashMap<String, String>,
+        unsettable: F,
+    ) -> Self
+    where
+        F: Fn(&str) -> bool,
+    {
+        if self.env_complete {
+            let mut unset: Vec<_> = baseline
+                .keys()
+                .filter(|name| !self.env.contains_key(*name))
+                .filter(|name| unsettable(name))
+                .cloned()
+                .collect();
+            unset.sort();
+            self.unset_env = unset;
+        }
+        self.env.retain(|name, val| baseline.get(name) != Some(val));
+        self
+    }
+
+    /// Remove all environment variables
     pub fn clear_env(mut self) -> Self {
         self.env.clear();
         self
     }
 
+    /// Add environment variable
     pub fn add_env<K, V>(mut self, k: K, v: V) -> Self
     where
         K: Into<String>,
@@ -344,6 +1015,7 @@ impl LaunchingData {
         self
     }
 
+    /// Replace all environment variables with single one
     pub fn set_env<K, V>(self, k: K, v: V) -> Self
     where
         K: Into<String>,
@@ -352,6 +1024,7 @@ impl LaunchingData {
         self.clear_env().add_env(k, v)
     }
 
+    /// Add environment variables
     pub fn add_envs<I, K, V>(self, it: I) -> Self
     where
         K: Into<String>,
@@ -361,6 +1034,7 @@ impl LaunchingData {
         it.fold(self, |s, (k, v)| s.add_env(k, v))
     }
 
+    /// Replace all environment variables
     pub fn set_envs<I, K, V>(self, it: I) -> Self
     where
         K: Into<String>,
@@ -1,9 +1,15 @@
-use std::io;
+use std::process::ExitCode;
 
 use niri_launcher::{Launcher, Parser};
 
-fn main() -> io::Result<()> {
+fn main() -> ExitCode {
     let args = Launcher::parse();
 
-    args.run()
+    match args.run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("niri-launcher: {err}");
+            ExitCode::from(err.exit_code())
+        }
+    }
 }
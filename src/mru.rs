@@ -0,0 +1,216 @@
+//!
+//! History of focused windows. The `daemon` command follows niri event stream
+//! and stores the ids of windows from the most recently focused one into
+//! [file](path). Other launcher instances read it with [History::load].
+//!
+
+use crate::{niri_request, Error, Launcher, Result};
+use niri_ipc::{Event, Request, Response};
+use std::{env, fs, io, path::PathBuf, time::Duration};
+
+/// Name of history file inside `$XDG_RUNTIME_DIR`
+const HISTORY_FILE: &str = "niri-launcher-mru";
+
+/// How long focus changes may be ignored for probing launcher
+const PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// The ids of niri windows from the most recently focused one
+#[derive(Debug, Default)]
+pub(crate) struct History {
+    ids: Vec<u64>,
+}
+
+/// Get location of history file
+///
+/// Lives within `$XDG_RUNTIME_DIR` as niri window ids are meaningless after
+/// session ends.
+pub(crate) fn path() -> PathBuf {
+    env::var_os("XDG_RUNTIME_DIR")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(env::temp_dir)
+        .join(HISTORY_FILE)
+}
+
+/// Order `windows` from the most recently opened one as a guess
+///
+/// Used as a stable base order when focus history lacks windows. Niri does
+/// not promise ids to increase, so larger id is only assumed to be newer,
+/// which holds for the current niri.
+pub(crate) fn sort_by_opening<T, F>(windows: &mut [T], id: F)
+where
+    F: Fn(&T) -> u64,
+{
+    windows.sort_by_key(|window| std::cmp::Reverse(id(window)));
+}
+
+/// Get location of file marking focus changes made by launcher itself
+fn probe_path() -> PathBuf {
+    path().with_extension("probe")
+}
+
+/// Mark the following focus changes as made by launcher to probe layout
+///
+/// Daemon ignores focus changes until the focus returns to `origin` window,
+/// so probing does not reorder history. Must be called before focus moves.
+pub(crate) fn begin_probe(origin: u64) -> io::Result<()> {
+    fs::write(probe_path(), format!("{origin}\n"))
+}
+
+/// Remove the mark when focus did not move
+///
+/// Otherwise daemon removes it once focus is back to `origin` window.
+pub(crate) fn cancel_probe() {
+    let _ = fs::remove_file(probe_path());
+}
+
+/// Get the window probing launcher returns focus to
+///
+/// The mark left by killed launcher is removed once it is stale.
+fn probe_origin() -> Option<u64> {
+    let path = probe_path();
+    let age = fs::metadata(&path).ok()?.modified().ok()?.elapsed();
+    if age.map_or(true, |age| age > PROBE_TIMEOUT) {
+        cancel_probe();
+        return None;
+    }
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+/// Whether `event` is focus change made by launcher probing layout
+fn is_probe(event: &Event) -> bool {
+    let (id, focused) = match event {
+        Event::WindowFocusChanged { id: Some(id) } => (*id, true),
+        Event::WindowOpenedOrChanged { window } if window.is_focused => {
+            (window.id, true)
+        }
+        // Focus can not return to closed window
+        Event::WindowClosed { id } => (*id, false),
+        _ => return false,
+    };
+    let Some(origin) = probe_origin() else {
+        return false;
+    };
+    if id == origin {
+        cancel_probe();
+    }
+    focused
+}
+
+impl History {
+    /// Read history stored by daemon
+    ///
+    /// The history is empty when daemon was never run.
+    pub fn load() -> Self {
+        let ids = fs::read_to_string(path())
+            .unwrap_or_default()
+            .lines()
+            .filter_map(|line| line.parse().ok())
+            .collect();
+        Self { ids }
+    }
+
+    /// The window ids from the most recently focused one
+    pub fn ids(&self) -> &[u64] {
+        &self.ids
+    }
+
+    /// Order `windows` from the most recently focused one
+    ///
+    /// Windows absent in history keep their order after the others.
+    pub fn sort<T, F>(&self, windows: &mut [T], id: F)
+    where
+        F: Fn(&T) -> u64,
+    {
+        windows.sort_by_key(|window| {
+            let id = id(window);
+            self.ids
+                .iter()
+                .position(|known| *known == id)
+                .unwrap_or(usize::MAX)
+        });
+    }
+
+    /// Write history for other launcher instances atomically
+    fn store(&self) -> io::Result<()> {
+        let path = path();
+        let tmp = path.with_extension("tmp");
+        let content: String =
+            self.ids.iter().map(|id| format!("{id}\n")).collect();
+        fs::write(&tmp, content)?;
+        fs::rename(tmp, path)
+    }
+
+    fn focus(&mut self, id: u64) {
+        self.ids.retain(|known| *known != id);
+        self.ids.insert(0, id);
+    }
+
+    /// Update history with niri event
+    ///
+    /// Returns whether the history was changed.
+    fn update(&mut self, event: Event) -> bool {
+        match event {
+            Event::WindowsChanged { windows } => {
+                // Keep the order of windows known from previous run
+                self.ids.retain(|id| windows.iter().any(|w| w.id == *id));
+                for window in &windows {
+                    if !self.ids.contains(&window.id) {
+                        self.ids.push(window.id);
+                    }
+                }
+                if let Some(window) = windows.iter().find(|w| w.is_focused) {
+                    self.focus(window.id);
+                }
+                true
+            }
+            Event::WindowOpenedOrChanged { window } if window.is_focused => {
+                let changed = self.ids.first() != Some(&window.id);
+                self.focus(window.id);
+                changed
+            }
+            Event::WindowOpenedOrChanged { window } => {
+                let known = self.ids.contains(&window.id);
+                if !known {
+                    self.ids.push(window.id);
+                }
+                !known
+            }
+            Event::WindowFocusChanged { id: Some(id) } => {
+                self.focus(id);
+                true
+            }
+            Event::WindowClosed { id } => {
+                let len = self.ids.len();
+                self.ids.retain(|known| *known != id);
+                len != self.ids.len()
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Launcher {
+    /// Record history of focused windows until niri exits
+    pub(crate) fn run_daemon(&self) -> Result<()> {
+        let mut socket = self.connect_niri()?;
+        match niri_request(&mut socket, Request::EventStream)? {
+            Response::Handled => (),
+            _ => {
+                return Err(Error::NiriProtocol(
+                    "Unexpected response to EventStream".into(),
+                ))
+            }
+        }
+        let mut read_event = socket.read_events();
+        let mut history = History::load();
+        // The mark may be left by launcher run without daemon
+        cancel_probe();
+        loop {
+            let event = read_event().map_err(Error::NiriConnection)?;
+            if !is_probe(&event) && history.update(event) {
+                history.store()?;
+            }
+        }
+    }
+}
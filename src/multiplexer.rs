@@ -0,0 +1,254 @@
+//!
+//! Terminal multiplexers awareness. When the foreground process of terminal
+//! is a `tmux` or `zellij` client its cwd is meaningless, so the server is
+//! asked for the active pane of client instead.
+//!
+
+use crate::{process, ssh, LaunchingData};
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// Format of `tmux display` output describing the active pane
+const TMUX_FORMAT: &str =
+    "#{pane_id}\t#{pane_pid}\t#{pane_tty}\t#{pane_current_path}";
+
+/// The tmux pane the context was taken from
+#[derive(Debug, Clone)]
+pub struct TmuxPane {
+    /// The tmux binary of client
+    pub command: PathBuf,
+    /// The socket options of client, e.g. `-L name`
+    pub socket: Vec<String>,
+    /// The pane id, e.g. `%3`
+    pub pane: String,
+}
+
+impl TmuxPane {
+    /// Get tmux command with socket options of client
+    pub fn command<I, S>(&self, args: I) -> Command
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<std::ffi::OsStr>,
+    {
+        let mut proc = Command::new(&self.command);
+        proc.args(&self.socket).args(args);
+        proc
+    }
+}
+
+/// Get context of active pane if process `pid` is multiplexer client
+pub(crate) fn get_launching_data(pid: i32) -> Option<LaunchingData> {
+    let cmdline = process::cmdline(pid).ok()?;
+    let program = Path::new(cmdline.first()?).file_name()?.to_str()?;
+    match program {
+        "tmux" => tmux(pid, &cmdline[1..]),
+        "zellij" => zellij(pid, &cmdline[1..]),
+        _ => None,
+    }
+}
+
+/// Get context of process running within pane
+///
+/// Takes the foreground process of pane terminal `tty`.
+fn pane_launching_data(pane_pid: i32, tty: &Path) -> Option<LaunchingData> {
+    let tty_nr = process::tty_nr(tty).ok()?;
+    let mut processes = vec![(pane_pid, process::stat(pane_pid).ok()?)];
+    processes.extend(process::descendants(pane_pid).ok()?);
+    let pid = process::foreground(&processes, tty_nr).unwrap_or(pane_pid);
+    process_launching_data(pid)
+}
+
+/// Get cwd and environment of process
+fn process_launching_data(pid: i32) -> Option<LaunchingData> {
+    let cwd = process::cwd(pid).ok();
+    let mut data = LaunchingData::default()
+        .maybe_cwd(cwd.as_deref().and_then(Path::to_str))
+        .set_envs(process::environ(pid).ok()?.into_iter())
+        .complete_env();
+    data.ssh = ssh::of_process(pid);
+    Some(data)
+}
+
+fn tmux(pid: i32, args: &[String]) -> Option<LaunchingData> {
+    let command = process::exe(pid).ok()?;
+    let socket = tmux_socket(args)?;
+    let client = process::tty(pid).ok()?;
+    let pane = TmuxPane {
+        command,
+        socket,
+        pane: String::new(),
+    };
+    let output = pane
+        .command(["display", "-p", "-c"])
+        .arg(client)
+        .arg(TMUX_FORMAT)
+        .output()
+        .ok()?;
+    let output = String::from_utf8(output.stdout).ok()?;
+    let mut fields = output.trim_end_matches('\n').splitn(4, '\t');
+    let (id, pane_pid, tty, cwd) = (
+        fields.next()?,
+        fields.next()?,
+        fields.next()?,
+        fields.next()?,
+    );
+
+    let data = pane_launching_data(pane_pid.parse().ok()?, Path::new(tty))
+        .unwrap_or_default();
+    let mut data = data.set_cwd(cwd);
+    data.tmux = Some(TmuxPane {
+        pane: id.into(),
+        ..pane
+    });
+    Some(data)
+}
+
+fn zellij(pid: i32, args: &[String]) -> Option<LaunchingData> {
+    let command = process::exe(pid).ok()?;
+    let servers: Vec<_> = process::all()
+        .ok()?
+        .filter_map(|pid| {
+            let cmdline = process::cmdline(pid).ok()?;
+            let server =
+                cmdline.iter().skip_while(|arg| *arg != "--server").nth(1)?;
+            let session = Path::new(server).file_name()?.to_str()?.to_string();
+            Some((pid, session))
+        })
+        .collect();
+    // The session is either given to client or the only running one
+    let session = zellij_session(args).or_else(|| match &servers[..] {
+        [(_, session)] => Some(session.clone()),
+        _ => None,
+    })?;
+    let (server, _) = servers.iter().find(|(_, name)| *name == session)?;
+
+    let output = Command::new(command)
+        .args(["--session", &session, "action", "list-clients"])
+        .output()
+        .ok()?;
+    let output = String::from_utf8(output.stdout).ok()?;
+    // The first line is header, e.g. `CLIENT_ID ZELLIJ_PANE_ID RUNNING_COMMAND`.
+    // Zellij does not tell which client runs within this terminal, so with
+    // several clients the pane is known only when all of them share it.
+    let mut panes = output
+        .lines()
+        .skip(1)
+        .map(|client| client.split_whitespace().nth(1));
+    let pane = panes.next()??;
+    if panes.any(|other| other != Some(pane)) {
+        return None;
+    }
+    let pane = pane.strip_prefix("terminal_")?.to_string();
+
+    // Zellij marks processes of pane with its id
+    let descendants = process::descendants(*server).ok()?;
+    let (shell, stat) = descendants.iter().find(|(pid, _)| {
+        process::environ(*pid).is_ok_and(|env| {
+            env.iter()
+                .any(|(name, val)| name == "ZELLIJ_PANE_ID" && *val == pane)
+        })
+    })?;
+    let pid = process::foreground(&descendants, stat.tty_nr).unwrap_or(*shell);
+    process_launching_data(pid)
+}
+
+/// Get socket options from arguments of tmux client
+///
+/// Only these are needed to reach the same server. Returns [None] when the
+/// arguments are invalid.
+fn tmux_socket(args: &[String]) -> Option<Vec<String>> {
+    let mut socket = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-L" | "-S" => socket.extend([arg.clone(), args.next()?.clone()]),
+            arg if arg.starts_with("-L") || arg.starts_with("-S") => {
+                socket.push(arg.into())
+            }
+            // The values of other global options are not socket ones
+            "-c" | "-f" | "-T" => {
+                args.next()?;
+            }
+            // The options of command follow its name
+            arg if !arg.starts_with('-') || arg == "--" => break,
+            _ => (),
+        }
+    }
+    Some(socket)
+}
+
+/// Get session name from arguments of zellij client
+fn zellij_session(args: &[String]) -> Option<String> {
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if let Some(session) = arg.strip_prefix("--session=") {
+            return Some(session.into());
+        }
+        match arg.as_str() {
+            "-s" | "--session" => return args.next().cloned(),
+            "attach" | "a" => break,
+            _ => (),
+        }
+    }
+    // The flags of attach, e.g. `-c` to create session, take no value and
+    // `--index` is used instead of session name
+    args.take_while(|arg| *arg != "--index")
+        .find(|arg| !arg.starts_with('-'))
+        .cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(cmdline: &str) -> Option<String> {
+        let args: Vec<_> =
+            cmdline.split_whitespace().map(String::from).collect();
+        zellij_session(&args)
+    }
+
+    fn socket(cmdline: &str) -> Option<Vec<String>> {
+        let args: Vec<_> =
+            cmdline.split_whitespace().map(String::from).collect();
+        tmux_socket(&args)
+    }
+
+    #[test]
+    fn tmux_socket_options() {
+        assert_eq!(socket(""), Some(vec![]));
+        assert_eq!(socket("attach -t main"), Some(vec![]));
+        assert_eq!(socket("-L work"), Some(vec!["-L".into(), "work".into()]));
+        assert_eq!(socket("-Lwork attach"), Some(vec!["-Lwork".into()]));
+        assert_eq!(
+            socket("-2 -S /tmp/tmux.sock new"),
+            Some(vec!["-S".into(), "/tmp/tmux.sock".into()])
+        );
+        assert_eq!(
+            socket("-f -L.conf -L work"),
+            Some(vec!["-L".into(), "work".into()])
+        );
+        assert_eq!(socket("new-session -S x"), Some(vec![]));
+    }
+
+    #[test]
+    fn tmux_socket_missing_value() {
+        assert_eq!(socket("-L"), None);
+        assert_eq!(socket("-u -S"), None);
+        assert_eq!(socket("-f"), None);
+    }
+
+    #[test]
+    fn zellij_session_name() {
+        assert_eq!(session(""), None);
+        assert_eq!(session("-s work"), Some("work".into()));
+        assert_eq!(session("--session=work"), Some("work".into()));
+        assert_eq!(session("attach work"), Some("work".into()));
+        assert_eq!(session("a -c work"), Some("work".into()));
+        assert_eq!(session("attach --create -f work"), Some("work".into()));
+        assert_eq!(session("--config c.kdl attach work"), Some("work".into()));
+        assert_eq!(session("attach --index 1"), None);
+        assert_eq!(session("attach -c"), None);
+    }
+}
@@ -0,0 +1,145 @@
+//!
+//! Neovim msgpack-RPC client. The [NvimSocket] sends requests to neovim
+//! server and waits for responses skipping notifications.
+//!
+
+use crate::{process, stream::Stream, Error, Result};
+use rmpv::Value;
+use std::io::{self, BufReader, BufWriter, Write};
+use std::{env, net::TcpStream, os::unix::net::UnixStream, path::PathBuf};
+
+/// The type of msgpack-RPC request message
+const REQUEST: u64 = 0;
+
+/// The type of msgpack-RPC response message
+const RESPONSE: u64 = 1;
+
+/// Connection to neovim server
+pub struct NvimSocket {
+    reader: BufReader<Stream>,
+    writer: BufWriter<Stream>,
+    msgid: u64,
+}
+
+impl NvimSocket {
+    /// Connect to neovim `--listen` address
+    ///
+    /// The address is either path of unix socket or `host:port`.
+    pub fn connect(address: &str) -> Result<Self> {
+        let stream = if address.contains('/') {
+            Stream::Unix(
+                UnixStream::connect(address).map_err(Error::NvimTransport)?,
+            )
+        } else {
+            Stream::Tcp(
+                TcpStream::connect(address).map_err(Error::NvimTransport)?,
+            )
+        };
+        let clone = match &stream {
+            Stream::Unix(stream) => stream.try_clone().map(Stream::Unix),
+            Stream::Tcp(stream) => stream.try_clone().map(Stream::Tcp),
+        }
+        .map_err(Error::NvimTransport)?;
+        Ok(Self {
+            reader: BufReader::new(stream),
+            writer: BufWriter::new(clone),
+            msgid: 0,
+        })
+    }
+
+    /// Call API `method` with `args` and wait for result
+    pub fn request(&mut self, method: &str, args: Vec<Value>) -> Result<Value> {
+        self.msgid += 1;
+        let request = Value::Array(vec![
+            REQUEST.into(),
+            self.msgid.into(),
+            method.into(),
+            Value::Array(args),
+        ]);
+        rmpv::encode::write_value(&mut self.writer, &request)
+            .map_err(|err| Error::NvimTransport(err.into()))?;
+        self.writer.flush().map_err(Error::NvimTransport)?;
+
+        loop {
+            let message = rmpv::decode::read_value(&mut self.reader)
+                .map_err(|err| Error::NvimTransport(err.into()))?;
+            let Value::Array(message) = message else {
+                return Err(invalid_data("Response is not an array"));
+            };
+            match &message[..] {
+                [kind, msgid, error, result]
+                    if kind.as_u64() == Some(RESPONSE)
+                        && msgid.as_u64() == Some(self.msgid) =>
+                {
+                    if !error.is_nil() {
+                        return Err(Error::NvimRejected(error_message(error)));
+                    }
+                    return Ok(result.clone());
+                }
+                // Notifications and requests from neovim are not interesting
+                _ => continue,
+            }
+        }
+    }
+
+    /// Evaluate lua `code` with `args` and get its result
+    pub fn exec_lua(&mut self, code: &str, args: Vec<Value>) -> Result<Value> {
+        self.request("nvim_exec_lua", vec![code.into(), Value::Array(args)])
+    }
+}
+
+/// Find address of the server of neovim process
+///
+/// Uses `--listen` argument and falls back to the default server address
+/// neovim creates within `$XDG_RUNTIME_DIR`.
+pub fn server_address(pid: i32) -> Option<String> {
+    let cmdline = process::cmdline(pid).ok()?;
+    let listen = cmdline
+        .iter()
+        .skip_while(|arg| *arg != "--listen")
+        .nth(1)
+        .cloned();
+    listen.or_else(|| {
+        let dir = env::var_os("XDG_RUNTIME_DIR").map(PathBuf::from)?;
+        let socket = dir.join(format!("nvim.{pid}.0"));
+        socket
+            .exists()
+            .then(|| socket.to_string_lossy().into_owned())
+    })
+}
+
+/// Find the neovim process among `pid` and its descendants
+///
+/// The process is recognized by the name of its executable.
+pub fn find_process(pid: i32) -> Option<i32> {
+    let is_nvim = |pid: i32| {
+        process::cmdline(pid).ok().and_then(|cmdline| {
+            let program = PathBuf::from(cmdline.first()?);
+            Some(program.file_name()? == "nvim")
+        }) == Some(true)
+    };
+    if is_nvim(pid) {
+        return Some(pid);
+    }
+    process::descendants(pid)
+        .ok()?
+        .into_iter()
+        .map(|(pid, _)| pid)
+        .find(|pid| is_nvim(*pid))
+}
+
+fn error_message(error: &Value) -> String {
+    match error {
+        // Neovim sends errors as `[type, message]`
+        Value::Array(error) => error
+            .get(1)
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .into(),
+        error => error.to_string(),
+    }
+}
+
+fn invalid_data(msg: &str) -> Error {
+    Error::NvimTransport(io::Error::new(io::ErrorKind::InvalidData, msg))
+}
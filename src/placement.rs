@@ -0,0 +1,200 @@
+//!
+//! Placement of launched window within niri. The [Watcher] subscribes to niri
+//! event stream before launch and waits for the new window of launched
+//! process. Then the [Placement] is applied with niri actions.
+//!
+
+use crate::{niri_request, process, Error, Result};
+use clap::{Args, ValueEnum};
+use niri_ipc::{
+    socket::Socket, Action, ColumnDisplay, Event, Request, Response,
+    SizeChange, Window, WorkspaceReferenceArg,
+};
+use std::{
+    collections::HashSet,
+    sync::mpsc,
+    thread,
+    time::{Duration, Instant},
+};
+
+/// How long to wait for the window of launched process
+const WINDOW_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Where to put the new window relative to the base window column
+#[derive(ValueEnum, Debug, Clone, Copy)]
+pub enum ColumnPlacement {
+    /// Into new column right after the base window one
+    Next,
+    /// Into the base window column
+    Consume,
+}
+
+/// Placement options of new window
+#[derive(Args, Debug, Clone, Default)]
+pub struct Placement {
+    /// Move new window to the workspace of base window
+    #[arg(long)]
+    pub same_workspace: bool,
+
+    /// Put new window next to or into the column of base window
+    ///
+    /// Implies `--same-workspace`.
+    #[arg(long)]
+    pub column: Option<ColumnPlacement>,
+
+    /// Make new window floating
+    #[arg(long)]
+    pub floating: bool,
+
+    /// Set width of new window, e.g. `800` or `50%`
+    #[arg(long, allow_hyphen_values = true)]
+    pub width: Option<SizeChange>,
+
+    /// Set height of new window, e.g. `600` or `50%`
+    #[arg(long, allow_hyphen_values = true)]
+    pub height: Option<SizeChange>,
+
+    /// Maximize the column of new window
+    #[arg(long)]
+    pub maximize: bool,
+
+    /// Display the column of new window as tabs
+    #[arg(long)]
+    pub tabbed: bool,
+}
+
+impl Placement {
+    /// Whether any placement option is set
+    pub fn is_requested(&self) -> bool {
+        self.same_workspace
+            || self.column.is_some()
+            || self.floating
+            || self.width.is_some()
+            || self.height.is_some()
+            || self.maximize
+            || self.tabbed
+    }
+
+    /// Get niri actions to place `window` relative to `base` one
+    pub fn actions(
+        &self,
+        window: &Window,
+        base: Option<&Window>,
+    ) -> Vec<Action> {
+        let id = Some(window.id);
+        let mut actions = Vec::new();
+        let workspace =
+            base.and_then(|base| Some((base.id, base.workspace_id?)));
+        if let Some((base, workspace)) = workspace {
+            if self.same_workspace || self.column.is_some() {
+                // Niri puts moved window right after the active column
+                actions.push(Action::FocusWindow { id: base });
+                actions.push(Action::MoveWindowToWorkspace {
+                    window_id: id,
+                    reference: WorkspaceReferenceArg::Id(workspace),
+                    focus: true,
+                });
+            }
+            if let Some(ColumnPlacement::Consume) = self.column {
+                actions.push(Action::ConsumeOrExpelWindowLeft { id });
+            }
+        }
+        if self.floating {
+            actions.push(Action::MoveWindowToFloating { id });
+        }
+        if let Some(change) = self.width {
+            actions.push(Action::SetWindowWidth { id, change });
+        }
+        if let Some(change) = self.height {
+            actions.push(Action::SetWindowHeight { id, change });
+        }
+        if self.maximize || self.tabbed {
+            // Column actions work with focused column only
+            actions.push(Action::FocusWindow { id: window.id });
+        }
+        if self.maximize {
+            actions.push(Action::MaximizeColumn {});
+        }
+        if self.tabbed {
+            actions.push(Action::SetColumnDisplay {
+                display: ColumnDisplay::Tabbed,
+            });
+        }
+        actions
+    }
+
+    /// Send placement actions to niri
+    pub fn apply(
+        &self,
+        socket: &mut Socket,
+        window: &Window,
+        base: Option<&Window>,
+    ) -> Result<()> {
+        for action in self.actions(window, base) {
+            match niri_request(socket, Request::Action(action))? {
+                Response::Handled => (),
+                _ => {
+                    return Err(Error::NiriProtocol(
+                        "Unexpected response to Action".into(),
+                    ))
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Watcher of windows opened after its creation
+pub struct Watcher {
+    windows: mpsc::Receiver<Window>,
+}
+
+impl Watcher {
+    /// Subscribe to niri events with `socket`
+    pub fn new(mut socket: Socket) -> Result<Self> {
+        match niri_request(&mut socket, Request::EventStream)? {
+            Response::Handled => (),
+            _ => {
+                return Err(Error::NiriProtocol(
+                    "Unexpected response to EventStream".into(),
+                ))
+            }
+        }
+        let mut read_event = socket.read_events();
+        let (sender, windows) = mpsc::channel();
+        thread::spawn(move || {
+            let mut known = HashSet::new();
+            while let Ok(event) = read_event() {
+                let window = match event {
+                    // The initial state is sent right after subscription
+                    Event::WindowsChanged { windows } => {
+                        known.extend(windows.iter().map(|window| window.id));
+                        continue;
+                    }
+                    Event::WindowOpenedOrChanged { window } => window,
+                    _ => continue,
+                };
+                // Stop once nobody waits for windows anymore
+                if known.insert(window.id) && sender.send(window).is_err() {
+                    break;
+                }
+            }
+        });
+        Ok(Self { windows })
+    }
+
+    /// Wait for new window of process `pid` or of its descendants
+    pub fn wait_for(&self, pid: i32) -> Option<Window> {
+        let deadline = Instant::now() + WINDOW_TIMEOUT;
+        loop {
+            let timeout = deadline.checked_duration_since(Instant::now())?;
+            let window = self.windows.recv_timeout(timeout).ok()?;
+            if window
+                .pid
+                .is_some_and(|wpid| process::ancestors(wpid).any(|p| p == pid))
+            {
+                return Some(window);
+            }
+        }
+    }
+}
@@ -0,0 +1,242 @@
+//!
+//! Helpers to inspect running processes through `/proc` filesystem.
+//!
+
+use std::fs::{self, read_link, File};
+use std::io::{self, BufRead};
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+
+/// Read environment variables of process
+///
+/// Variables which are not valid utf-8 are skipped.
+pub fn environ(pid: i32) -> io::Result<Vec<(String, String)>> {
+    let environ = File::open(format!("/proc/{pid}/environ"))?;
+    let lines = io::BufReader::new(environ).split(0x0);
+    Ok(lines
+        .filter_map(|line| {
+            let line = String::from_utf8(line.ok()?).ok()?;
+            let (k, v) = line.split_once("=")?;
+            Some((k.to_string(), v.to_string()))
+        })
+        .collect())
+}
+
+/// Read command line arguments of process
+pub fn cmdline(pid: i32) -> io::Result<Vec<String>> {
+    let cmdline = fs::read(format!("/proc/{pid}/cmdline"))?;
+    Ok(cmdline
+        .split(|&byte| byte == 0)
+        .filter(|arg| !arg.is_empty())
+        .map(|arg| String::from_utf8_lossy(arg).into_owned())
+        .collect())
+}
+
+/// Read path of executable of process
+pub fn exe(pid: i32) -> io::Result<PathBuf> {
+    read_link(format!("/proc/{pid}/exe"))
+}
+
+/// Read path of terminal the standard input of process is connected to
+pub fn tty(pid: i32) -> io::Result<PathBuf> {
+    read_link(format!("/proc/{pid}/fd/0"))
+}
+
+/// Read current working directory of process
+pub fn cwd(pid: i32) -> io::Result<PathBuf> {
+    read_link(format!("/proc/{pid}/cwd"))
+}
+
+/// The fields of `/proc/<pid>/stat` launcher is interested in
+#[derive(Debug, Clone, Copy)]
+pub struct Stat {
+    /// Pid of parent process
+    pub ppid: i32,
+    /// Controlling terminal, zero if there is no one
+    pub tty_nr: i32,
+    /// Foreground process group of controlling terminal
+    pub tpgid: i32,
+    /// Time the process started after system boot in clock ticks
+    pub starttime: u64,
+}
+
+/// Read status of process
+pub fn stat(pid: i32) -> io::Result<Stat> {
+    let stat = fs::read_to_string(format!("/proc/{pid}/stat"))?;
+    parse_stat(&stat).ok_or(io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("Invalid /proc/{pid}/stat format"),
+    ))
+}
+
+/// Parse content of `/proc/<pid>/stat`
+fn parse_stat(stat: &str) -> Option<Stat> {
+    // The second field is executable name in parentheses which may contain
+    // spaces and parentheses itself, so skip to the last one.
+    let fields: Vec<_> = stat
+        .rsplit_once(')')
+        .map(|(_, rest)| rest.split_whitespace().collect())
+        .unwrap_or_default();
+    // The fields are numbered from the state one which is third
+    let field = |n: usize| fields.get(n - 3).copied();
+    Some(Stat {
+        ppid: field(4)?.parse().ok()?,
+        tty_nr: field(7)?.parse().ok()?,
+        tpgid: field(8)?.parse().ok()?,
+        starttime: field(22)?.parse().ok()?,
+    })
+}
+
+/// Read pid of parent process
+pub fn parent(pid: i32) -> io::Result<i32> {
+    stat(pid).map(|stat| stat.ppid)
+}
+
+/// Iterate over process and its ancestors up to the init one
+///
+/// Stops at the first process which is not accessible.
+pub fn ancestors(pid: i32) -> impl Iterator<Item = i32> {
+    std::iter::successors(Some(pid), |&pid| parent(pid).ok())
+        .take_while(|&pid| pid > 1)
+}
+
+/// List pids of direct children of process
+pub fn children(pid: i32) -> io::Result<Vec<i32>> {
+    Ok(all()?
+        .filter(|&child| parent(child).is_ok_and(|parent| parent == pid))
+        .collect())
+}
+
+/// List all descendants of process with their status
+///
+/// Parents go before their children.
+pub fn descendants(pid: i32) -> io::Result<Vec<(i32, Stat)>> {
+    let all: Vec<_> = all()?
+        .filter_map(|pid| stat(pid).ok().map(|stat| (pid, stat)))
+        .collect();
+    let mut found = Vec::new();
+    let mut parents = vec![pid];
+    while let Some(parent) = parents.pop() {
+        for &(pid, stat) in all.iter().filter(|(_, stat)| stat.ppid == parent) {
+            found.push((pid, stat));
+            parents.push(pid);
+        }
+    }
+    Ok(found)
+}
+
+/// Find the foreground process of terminal `tty_nr` among `processes`
+///
+/// Falls back to the outermost process of terminal when the leader of
+/// foreground process group is not among `processes`, e.g. it has exited.
+pub fn foreground(processes: &[(i32, Stat)], tty_nr: i32) -> Option<i32> {
+    let on_tty: Vec<_> = processes
+        .iter()
+        .filter(|(_, stat)| stat.tty_nr == tty_nr)
+        .collect();
+    let (outermost, stat) = on_tty.iter().find(|(_, stat)| {
+        !on_tty.iter().any(|(parent, _)| *parent == stat.ppid)
+    })?;
+    on_tty
+        .iter()
+        .find(|(pid, _)| *pid == stat.tpgid)
+        .map_or(Some(*outermost), |(pid, _)| Some(*pid))
+}
+
+/// Get the device number of terminal in the form of `tty_nr` of [Stat]
+pub fn tty_nr(path: &Path) -> io::Result<i32> {
+    Ok(encode_tty_nr(fs::metadata(path)?.rdev()))
+}
+
+/// Convert device number of glibc `dev_t` layout into kernel `tty_nr` one
+fn encode_tty_nr(rdev: u64) -> i32 {
+    let major = ((rdev >> 8) & 0xfff) | ((rdev >> 32) & !0xfff);
+    let minor = (rdev & 0xff) | ((rdev >> 12) & !0xff);
+    ((minor & 0xff) | (major << 8) | ((minor & !0xff) << 12)) as i32
+}
+
+/// List pids of all processes
+pub fn all() -> io::Result<impl Iterator<Item = i32>> {
+    Ok(fs::read_dir("/proc")?
+        .filter_map(|entry| entry.ok()?.file_name().to_str()?.parse().ok()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Status line of process with given `comm` and usual other fields
+    fn stat_line(comm: &str) -> String {
+        format!(
+            "1234 ({comm}) S 1000 1234 1234 34817 1300 4194304 100 0 0 0 \
+             1 2 0 0 20 0 1 0 987654 1000000 500 18446744073709551615"
+        )
+    }
+
+    #[test]
+    fn parse_stat_fields() {
+        let stat = parse_stat(&stat_line("bash")).unwrap();
+        assert_eq!(stat.ppid, 1000);
+        assert_eq!(stat.tty_nr, 34817);
+        assert_eq!(stat.tpgid, 1300);
+        assert_eq!(stat.starttime, 987654);
+    }
+
+    #[test]
+    fn parse_stat_tricky_comm() {
+        for comm in ["(a) b)", "a b", ") 1 2 3", "", "x (y"] {
+            let stat = parse_stat(&stat_line(comm)).unwrap();
+            assert_eq!(stat.ppid, 1000, "comm {comm:?}");
+            assert_eq!(stat.starttime, 987654, "comm {comm:?}");
+        }
+    }
+
+    #[test]
+    fn parse_stat_rejects_truncated() {
+        assert!(parse_stat("1234 (bash) S 1000").is_none());
+        assert!(parse_stat("").is_none());
+    }
+
+    #[test]
+    fn stat_of_self() {
+        let stat = stat(std::process::id() as i32).unwrap();
+        assert_eq!(stat.ppid, parent(std::process::id() as i32).unwrap());
+        assert!(stat.starttime > 0);
+    }
+
+    #[test]
+    fn tty_nr_encoding() {
+        // The glibc `makedev(major, minor)`
+        let makedev = |major: u64, minor: u64| {
+            ((major & 0xfffff000) << 32)
+                | ((major & 0xfff) << 8)
+                | ((minor & 0xffffff00) << 12)
+                | (minor & 0xff)
+        };
+        // The kernel `new_encode_dev(MKDEV(major, minor))`
+        let tty_nr = |major: i32, minor: i32| {
+            (minor & 0xff) | (major << 8) | ((minor & !0xff) << 12)
+        };
+        for (major, minor) in
+            [(136, 0), (136, 3), (136, 300), (4, 1), (4096, 1)]
+        {
+            assert_eq!(
+                encode_tty_nr(makedev(major as u64, minor as u64)),
+                tty_nr(major, minor),
+                "device {major}:{minor}"
+            );
+        }
+        assert_eq!(encode_tty_nr(makedev(136, 3)), 34819);
+    }
+
+    #[test]
+    fn tty_nr_of_own_terminal() {
+        use std::io::IsTerminal;
+        if !io::stdin().is_terminal() {
+            return;
+        }
+        let pid = std::process::id() as i32;
+        let tty = tty(pid).unwrap();
+        assert_eq!(tty_nr(&tty).unwrap(), stat(pid).unwrap().tty_nr);
+    }
+}
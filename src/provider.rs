@@ -0,0 +1,154 @@
+//!
+//! Context providers. The [ContextProvider] extracts [LaunchingData] from the
+//! base niri window. The [ProviderRegistry] chooses the provider for window
+//! by matching its app_id against registered patterns.
+//!
+
+use crate::{Error, LaunchingData, Result};
+use std::{
+    env, fmt,
+    path::{Path, PathBuf},
+};
+
+mod kitty;
+mod neovide;
+mod procfs;
+mod terminal;
+mod wezterm;
+
+pub use kitty::{KittyContext, KittyProvider};
+pub use neovide::{NeovideProvider, NvimContext};
+pub use procfs::ProcfsProvider;
+pub use terminal::TerminalProvider;
+pub use wezterm::WeztermProvider;
+
+/// Source of launching context for niri windows
+pub trait ContextProvider {
+    /// Short name of provider for diagnostics
+    fn name(&self) -> &str;
+
+    /// Get launching data from the window
+    fn get_launching_data(
+        &self,
+        window: &niri_ipc::Window,
+    ) -> Result<LaunchingData>;
+}
+
+/// The ordered list of providers with app_id patterns they are used for
+///
+/// The first provider which pattern matches the whole app_id of window wins.
+#[derive(Default)]
+pub struct ProviderRegistry {
+    providers: Vec<(regex::Regex, Box<dyn ContextProvider>)>,
+}
+
+impl ProviderRegistry {
+    /// Create empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register provider for windows which app_id matches `app_id` regex
+    pub fn register<P>(&mut self, app_id: &str, provider: P) -> Result<()>
+    where
+        P: ContextProvider + 'static,
+    {
+        let pattern = app_id_pattern(app_id)?;
+        self.providers.push((pattern, Box::new(provider)));
+        Ok(())
+    }
+
+    /// Move all providers of `other` to the end of this registry
+    pub fn append(&mut self, other: &mut Self) {
+        self.providers.append(&mut other.providers);
+    }
+
+    /// Find provider for app_id
+    pub fn find(&self, app_id: &str) -> Option<&dyn ContextProvider> {
+        self.providers
+            .iter()
+            .find(|(pattern, _)| pattern.is_match(app_id))
+            .map(|(_, provider)| provider.as_ref())
+    }
+
+    /// Get launching data from window with matching provider
+    pub fn get_launching_data(
+        &self,
+        window: &niri_ipc::Window,
+    ) -> Result<LaunchingData> {
+        let class =
+            window.app_id.as_ref().ok_or(Error::ProviderUnsupported(
+                "Focused niri window does not have class".into(),
+            ))?;
+        let provider = self.find(class).ok_or(Error::ProviderUnsupported(
+            format!("Can not get launching data from {class}"),
+        ))?;
+        let mut data = provider.get_launching_data(window)?;
+        data.provider.get_or_insert_with(|| provider.name().into());
+        Ok(data)
+    }
+}
+
+impl fmt::Debug for ProviderRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(
+                self.providers.iter().map(|(pattern, provider)| {
+                    (pattern.as_str(), provider.name())
+                }),
+            )
+            .finish()
+    }
+}
+
+/// Compile regex which matches the whole app_id
+pub(crate) fn app_id_pattern(app_id: &str) -> Result<regex::Regex> {
+    regex::Regex::new(&format!("^(?:{app_id})$"))
+        .map_err(|err| Error::Config(format!("Invalid app_id pattern: {err}")))
+}
+
+/// Replace `${ENV}` and `$ENV` constructions and leading `~` in `value`
+pub(crate) fn expand_env(value: &str) -> String {
+    let envre = regex::Regex::new(r"\$(?:\{([^\{\}\s]*)\}|(\w+))").unwrap();
+
+    let value = envre.replace_all(value, |caps: &regex::Captures| {
+        let name = caps.get(1).or(caps.get(2)).unwrap().as_str();
+        env::var(name).unwrap_or_default()
+    });
+    match value.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') => {
+            format!("{}{rest}", env::var("HOME").unwrap_or_default())
+        }
+        _ => value.into_owned(),
+    }
+}
+
+/// Expand `value` like [expand_env] if all the variables are set
+///
+/// Returns [None] when any of variables is unset or empty, as the result
+/// would point to unrelated location, e.g. to the root directory.
+pub(crate) fn try_expand_env(value: &str) -> Option<String> {
+    let envre = regex::Regex::new(r"\$(?:\{([^\{\}\s]*)\}|(\w+))").unwrap();
+    let home = value.starts_with('~').then_some("HOME");
+    let mut names = envre
+        .captures_iter(value)
+        .map(|caps| caps.get(1).or(caps.get(2)).unwrap().as_str())
+        .chain(home);
+    names
+        .all(|name| env::var_os(name).is_some_and(|value| !value.is_empty()))
+        .then(|| expand_env(value))
+}
+
+/// Find the closest directory with `.git` among `dir` and its parents
+pub(crate) fn git_root(dir: &Path) -> Option<PathBuf> {
+    dir.ancestors()
+        .find(|dir| dir.join(".git").exists())
+        .map(Path::to_path_buf)
+}
+
+/// Get pid of window or fail
+pub(crate) fn window_pid(window: &niri_ipc::Window) -> Result<i32> {
+    window.pid.ok_or(Error::ProviderUnsupported(
+        "Focused niri window does not have pid".into(),
+    ))
+}
@@ -0,0 +1,489 @@
+use super::{expand_env, window_pid, ContextProvider};
+use crate::{kitty, multiplexer, process, ssh, Error, LaunchingData, Result};
+use clap::ValueEnum;
+use serde::Deserialize;
+use std::{env, fmt, fs, io, path::Path, path::PathBuf};
+
+/// Provider which asks kitty for its focused window over remote control
+#[derive(Debug, Clone)]
+pub struct KittyProvider {
+    socket: String,
+    context: KittyContext,
+    password: Option<String>,
+    cwd_user_var: Option<String>,
+}
+
+/// The way the kitty socket was found with
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum SocketSource {
+    /// The socket template from arguments or configuration
+    Template,
+    /// The `KITTY_LISTEN_ON` variable of kitty child process
+    Environ,
+    /// The `listen_on` option of `kitty.conf`
+    Config,
+}
+
+/// The connected kitty socket with the way it was found
+pub(crate) struct DiscoveredSocket {
+    pub socket: kitty::KittySocket,
+    pub source: SocketSource,
+    pub address: String,
+}
+
+/// Where to take the context of kitty window from
+#[derive(ValueEnum, Deserialize, Debug, Clone, Copy, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum KittyContext {
+    /// The cwd and environment reported by kitty
+    ///
+    /// The environment contains only variables set by kitty on launch.
+    Kitty,
+
+    /// The cwd and environment of foreground process of kitty window
+    ///
+    /// Reflects changes made within the shell, e.g. `export` or `cd`. Falls
+    /// back to kitty reported data when process is not accessible.
+    #[default]
+    Process,
+}
+
+impl KittyProvider {
+    /// Create provider with template of kitty socket
+    ///
+    /// The template accepts environment variables in view `${ENV}` and `{pid}`
+    /// construction which will be replaced with pid of target kitty process.
+    pub fn new<S>(socket: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            socket: socket.into(),
+            context: KittyContext::default(),
+            password: None,
+            cwd_user_var: None,
+        }
+    }
+
+    /// Set password for kitty remote control
+    ///
+    /// With password commands are encrypted with kitty public key taken from
+    /// environment of kitty children.
+    pub fn with_password(mut self, password: Option<String>) -> Self {
+        self.password = password;
+        self
+    }
+
+    /// Set the source of window context
+    pub fn with_context(mut self, context: KittyContext) -> Self {
+        self.context = context;
+        self
+    }
+
+    /// Set the user variable remote shell reports its cwd with
+    ///
+    /// Used to open ssh connection in the same directory.
+    pub fn with_cwd_user_var(mut self, name: Option<String>) -> Self {
+        self.cwd_user_var = name;
+        self
+    }
+
+    /// Connect to socket of kitty process with `pid`
+    pub(crate) fn connect(&self, pid: i32) -> Result<kitty::KittySocket> {
+        self.discover(pid).map(|found| found.socket)
+    }
+
+    /// Find and connect to socket of kitty process with `pid`
+    ///
+    /// Tries the socket template first, then `KITTY_LISTEN_ON` variable of
+    /// kitty child processes and then `listen_on` option of `kitty.conf`.
+    pub(crate) fn discover(&self, pid: i32) -> Result<DiscoveredSocket> {
+        let template =
+            expand_env(&self.socket).replace("{pid}", &pid.to_string());
+        let children_env: Vec<_> = process::children(pid)
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|child| process::environ(child).ok())
+            .collect();
+        let children_var = |var: &'static str| {
+            children_env.iter().filter_map(move |env| {
+                env.iter()
+                    .find(|(name, _)| name == var)
+                    .map(|(_, val)| val.clone())
+            })
+        };
+
+        let mut candidates = vec![(SocketSource::Template, template)];
+        candidates.extend(
+            children_var("KITTY_LISTEN_ON")
+                .map(|address| (SocketSource::Environ, address)),
+        );
+        if let Some(listen_on) = config_listen_on() {
+            if let Some(address) = expand_listen_on(&listen_on, pid) {
+                candidates.push((SocketSource::Config, address));
+            }
+        }
+
+        let mut error = Error::KittyTransport(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("Can not find socket of kitty {pid}"),
+        ));
+        let socket = candidates.into_iter().find_map(|(source, address)| {
+            kitty::KittySocket::connect(&address)
+                .map_err(|err| error = err)
+                .ok()
+                .map(|socket| (socket, source, address))
+        });
+        let Some((socket, source, address)) = socket else {
+            return Err(error);
+        };
+
+        let socket = if let Some(password) = self.password.as_ref() {
+            // Our own environment is useful only when we are run from the
+            // same kitty instance
+            let own_key = env::var("KITTY_PID")
+                .is_ok_and(|kitty_pid| kitty_pid == pid.to_string())
+                .then(|| env::var("KITTY_PUBLIC_KEY").ok())
+                .flatten();
+            let public_key = children_var("KITTY_PUBLIC_KEY")
+                .next()
+                .or(own_key)
+                .ok_or(Error::KittyTransport(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("Can not find public key of kitty {pid}"),
+                )))?;
+            socket.with_password(password.clone(), &public_key)?
+        } else {
+            socket
+        };
+        Ok(DiscoveredSocket {
+            socket,
+            source,
+            address,
+        })
+    }
+
+    fn find_focused_window(
+        windows: Vec<kitty::OsWindow>,
+    ) -> Option<kitty::Window> {
+        for window in windows {
+            if window.is_focused {
+                for tab in window.tabs {
+                    if tab.is_focused {
+                        for window in tab.windows {
+                            if window.is_focused {
+                                return Some(window);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /// Find the innermost foreground process of kitty window
+    ///
+    /// This is the one which is not a parent of other foreground processes.
+    fn find_foreground_process(
+        window: &kitty::Window,
+    ) -> Option<&kitty::Process> {
+        let processes = &window.foreground_processes;
+        let parents: Vec<i32> = processes
+            .iter()
+            .filter_map(|proc| process::parent(proc.pid).ok())
+            .collect();
+        processes
+            .iter()
+            .rev()
+            .find(|proc| !parents.contains(&proc.pid))
+    }
+
+    /// Find ssh connection running within kitty window
+    ///
+    /// The ssh kitten runs the real ssh as its child within the same process
+    /// group, so the outermost foreground connection is taken and the kitten
+    /// is preferred.
+    fn find_ssh(&self, window: &kitty::Window) -> Option<ssh::SshTarget> {
+        let processes = &window.foreground_processes;
+        let pids: Vec<i32> = processes.iter().map(|proc| proc.pid).collect();
+        let mut targets: Vec<_> = processes
+            .iter()
+            .filter_map(|proc| {
+                let depth = process::ancestors(proc.pid)
+                    .filter(|pid| pids.contains(pid))
+                    .count();
+                Some((depth, ssh::SshTarget::parse(&proc.cmdline)?))
+            })
+            .collect();
+        targets.sort_by_key(|(depth, target)| (!target.is_kitten(), *depth));
+        let target = match targets.into_iter().next() {
+            Some((_, target)) => target,
+            None => ssh::SshTarget::parse(&window.cmdline)?,
+        };
+        let cwd = self
+            .cwd_user_var
+            .as_ref()
+            .and_then(|name| window.user_vars.get(name))
+            .cloned();
+        Some(target.with_cwd(cwd))
+    }
+
+    fn get_launching_data_from_process(
+        window: &kitty::Window,
+    ) -> io::Result<LaunchingData> {
+        let proc =
+            Self::find_foreground_process(window).ok_or(io::Error::new(
+                io::ErrorKind::NotFound,
+                "No foreground process in kitty window",
+            ))?;
+        if let Some(data) = multiplexer::get_launching_data(proc.pid) {
+            return Ok(data);
+        }
+        let env = process::environ(proc.pid)?;
+        let cwd = process::cwd(proc.pid)
+            .ok()
+            .or(proc.cwd.clone())
+            .unwrap_or(window.cwd.clone());
+        Ok(LaunchingData::default()
+            .maybe_cwd(cwd.to_str())
+            .set_envs(env.into_iter())
+            .complete_env())
+    }
+}
+
+impl ContextProvider for KittyProvider {
+    fn name(&self) -> &str {
+        "kitty"
+    }
+
+    fn get_launching_data(
+        &self,
+        window: &niri_ipc::Window,
+    ) -> Result<LaunchingData> {
+        let pid = window_pid(window)?;
+        let mut socket = self.connect(pid)?;
+        let r = kitty::Command::Ls(kitty::Ls::default());
+        let r = socket.request(r)?;
+        let windows: Vec<kitty::OsWindow> =
+            serde_json::from_value(r).map_err(|err| {
+                Error::KittyTransport(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    err,
+                ))
+            })?;
+        let window = Self::find_focused_window(windows).ok_or(
+            Error::ProviderUnsupported("No focused kitty window".into()),
+        )?;
+        let ssh = self.find_ssh(&window);
+        let data = match self.context {
+            KittyContext::Process => {
+                Self::get_launching_data_from_process(&window).ok()
+            }
+            KittyContext::Kitty => None,
+        };
+        let mut data = data.unwrap_or_else(|| {
+            LaunchingData::default()
+                .maybe_cwd(window.cwd.to_str())
+                .set_envs(window.env.into_iter())
+        });
+        if data.ssh.is_none() {
+            data.ssh = ssh;
+        }
+        Ok(data)
+    }
+}
+
+impl fmt::Display for SocketSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Template => "socket template",
+            Self::Environ => "KITTY_LISTEN_ON of kitty children",
+            Self::Config => "listen_on from kitty.conf",
+        })
+    }
+}
+
+/// Get kitty configuration directory
+fn config_dir() -> Option<PathBuf> {
+    env::var_os("KITTY_CONFIG_DIRECTORY")
+        .map(PathBuf::from)
+        .or_else(|| {
+            env::var_os("XDG_CONFIG_HOME")
+                .filter(|dir| !dir.is_empty())
+                .map(|dir| PathBuf::from(dir).join("kitty"))
+        })
+        .or_else(|| {
+            env::var_os("HOME")
+                .map(|home| PathBuf::from(home).join(".config").join("kitty"))
+        })
+}
+
+/// Get value of `listen_on` option from `kitty.conf`
+fn config_listen_on() -> Option<String> {
+    let dir = config_dir()?;
+    let mut listen_on = None;
+    parse_config(&dir, &dir.join("kitty.conf"), &mut listen_on, 0);
+    listen_on
+}
+
+/// Expand `listen_on` option of `kitty.conf` for kitty `pid` as kitty does
+///
+/// Kitty appends `-{kitty_pid}` only to unix sockets without it and treats
+/// `none` as disabled listening.
+fn expand_listen_on(listen_on: &str, pid: i32) -> Option<String> {
+    if listen_on == "none" {
+        return None;
+    }
+    let mut listen_on = expand_env(listen_on);
+    if listen_on.starts_with("unix:") && !listen_on.contains("{kitty_pid}") {
+        listen_on.push_str("-{kitty_pid}");
+    }
+    let listen_on = listen_on.replace("{kitty_pid}", &pid.to_string());
+    match listen_on.strip_prefix("unix:~") {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') => {
+            let home = env::var("HOME").unwrap_or_default();
+            Some(format!("unix:{home}{rest}"))
+        }
+        _ => Some(listen_on),
+    }
+}
+
+/// Parse kitty configuration file following `include` directives
+fn parse_config(
+    dir: &Path,
+    path: &Path,
+    listen_on: &mut Option<String>,
+    depth: usize,
+) {
+    // Guard against include loops
+    if depth > 16 {
+        return;
+    }
+    let Ok(content) = fs::read_to_string(path) else {
+        return;
+    };
+    for line in content.lines().map(str::trim) {
+        if line.starts_with('#') {
+            continue;
+        }
+        let Some((key, value)) = line.split_once(char::is_whitespace) else {
+            continue;
+        };
+        let value = value.trim();
+        match key {
+            "listen_on" => *listen_on = Some(value.into()),
+            "include" => {
+                let include = dir.join(expand_env(value));
+                parse_config(dir, &include, listen_on, depth + 1);
+            }
+            _ => (),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Create configuration directory with `files` for test `name`
+    fn config(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir = env::temp_dir()
+            .join(format!("niri-launcher-test-{}-{name}", std::process::id()));
+        for (path, content) in files {
+            let path = dir.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+        dir
+    }
+
+    fn listen_on(dir: &Path) -> Option<String> {
+        let mut listen_on = None;
+        parse_config(dir, &dir.join("kitty.conf"), &mut listen_on, 0);
+        fs::remove_dir_all(dir).unwrap();
+        listen_on
+    }
+
+    #[test]
+    fn parse_config_skips_comments() {
+        let dir = config(
+            "comments",
+            &[(
+                "kitty.conf",
+                "# listen_on unix:/commented\n  # indented comment\n\
+                 font_size 12\nlisten_on unix:/tmp/kitty\n",
+            )],
+        );
+        assert_eq!(listen_on(&dir).as_deref(), Some("unix:/tmp/kitty"));
+    }
+
+    #[test]
+    fn parse_config_accepts_tabs() {
+        let dir =
+            config("tabs", &[("kitty.conf", "listen_on\t unix:@kitty\n")]);
+        assert_eq!(listen_on(&dir).as_deref(), Some("unix:@kitty"));
+    }
+
+    #[test]
+    fn parse_config_follows_includes() {
+        let dir = config(
+            "includes",
+            &[
+                (
+                    "kitty.conf",
+                    "listen_on unix:/first\ninclude conf/rc.conf\n",
+                ),
+                ("conf/rc.conf", "include remote.conf\n"),
+                ("remote.conf", "listen_on tcp:localhost:12345\n"),
+            ],
+        );
+        assert_eq!(listen_on(&dir).as_deref(), Some("tcp:localhost:12345"));
+    }
+
+    #[test]
+    fn parse_config_last_value_wins() {
+        let dir = config(
+            "last",
+            &[
+                ("kitty.conf", "include other.conf\nlisten_on unix:/last\n"),
+                ("other.conf", "listen_on unix:/included\n"),
+            ],
+        );
+        assert_eq!(listen_on(&dir).as_deref(), Some("unix:/last"));
+    }
+
+    #[test]
+    fn parse_config_survives_include_loop() {
+        let dir = config(
+            "loop",
+            &[("kitty.conf", "listen_on none\ninclude kitty.conf\n")],
+        );
+        assert_eq!(listen_on(&dir).as_deref(), Some("none"));
+    }
+
+    #[test]
+    fn listen_on_expansion() {
+        let expand = |value| expand_listen_on(value, 42);
+        assert_eq!(expand("none"), None);
+        assert_eq!(expand("unix:/tmp/kitty").unwrap(), "unix:/tmp/kitty-42");
+        assert_eq!(expand("unix:@kitty").unwrap(), "unix:@kitty-42");
+        assert_eq!(
+            expand("unix:/tmp/kitty-{kitty_pid}.sock").unwrap(),
+            "unix:/tmp/kitty-42.sock"
+        );
+        assert_eq!(
+            expand("tcp:localhost:12345").unwrap(),
+            "tcp:localhost:12345"
+        );
+        assert_eq!(
+            expand("tcp:localhost:{kitty_pid}").unwrap(),
+            "tcp:localhost:42"
+        );
+        let home = env::var("HOME").unwrap_or_default();
+        assert_eq!(
+            expand("unix:~/kitty").unwrap(),
+            format!("unix:{home}/kitty-42")
+        );
+    }
+}
@@ -0,0 +1,96 @@
+use super::{git_root, window_pid, ContextProvider};
+use crate::{nvim, process, Error, LaunchingData, Result};
+use clap::ValueEnum;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+/// Lua code returning tab cwd, name and type of current buffer
+const STATE_LUA: &str =
+    "return { vim.fn.getcwd(-1, 0), vim.api.nvim_buf_get_name(0), vim.bo.buftype }";
+
+/// Provider which asks neovim embedded into neovide for its state
+///
+/// Falls back to cwd of neovim process if its server is not accessible.
+#[derive(Debug, Clone, Default)]
+pub struct NeovideProvider {
+    context: NvimContext,
+}
+
+/// Which directory of neovim to use as cwd
+#[derive(ValueEnum, Deserialize, Debug, Clone, Copy, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum NvimContext {
+    /// The cwd of current tab
+    #[default]
+    Cwd,
+    /// The directory of current buffer
+    BufferDir,
+    /// The git root of current buffer or of tab cwd
+    GitRoot,
+}
+
+impl NeovideProvider {
+    /// Create provider
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the source of cwd
+    pub fn with_context(mut self, context: NvimContext) -> Self {
+        self.context = context;
+        self
+    }
+
+    /// Ask neovim process with `pid` for directory of chosen context
+    fn get_cwd(&self, pid: i32) -> Result<PathBuf> {
+        let address = nvim::server_address(pid).ok_or(
+            Error::ProviderUnsupported(format!("No server of neovim {pid}")),
+        )?;
+        let mut socket = nvim::NvimSocket::connect(&address)?;
+        let state = socket.exec_lua(STATE_LUA, vec![])?;
+        let state: Vec<_> = state
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|value| value.as_str().unwrap_or_default())
+            .collect();
+        let [cwd, buffer, buftype] = state[..] else {
+            return Err(Error::NvimRejected("Unexpected state".into()));
+        };
+        let cwd = PathBuf::from(cwd);
+        // Special buffers, e.g. terminal or help, have no useful directory
+        let buffer_dir = (buftype.is_empty() && !buffer.is_empty())
+            .then(|| Path::new(buffer).parent().map(Path::to_path_buf))
+            .flatten();
+        Ok(match self.context {
+            NvimContext::Cwd => cwd,
+            NvimContext::BufferDir => buffer_dir.unwrap_or(cwd),
+            NvimContext::GitRoot => buffer_dir
+                .as_deref()
+                .and_then(git_root)
+                .or_else(|| git_root(&cwd))
+                .unwrap_or(cwd),
+        })
+    }
+}
+
+impl ContextProvider for NeovideProvider {
+    fn name(&self) -> &str {
+        "neovide"
+    }
+
+    fn get_launching_data(
+        &self,
+        window: &niri_ipc::Window,
+    ) -> Result<LaunchingData> {
+        let pid = window_pid(window)?;
+        let pid = nvim::find_process(pid).ok_or(Error::ProviderUnsupported(
+            format!("No neovim within neovide {pid}"),
+        ))?;
+        let cwd = self.get_cwd(pid).or_else(|_| process::cwd(pid))?;
+        Ok(LaunchingData::default()
+            .maybe_cwd(cwd.to_str())
+            .set_envs(process::environ(pid)?.into_iter())
+            .complete_env())
+    }
+}
@@ -0,0 +1,33 @@
+use super::{window_pid, ContextProvider};
+use crate::{process, LaunchingData, Result};
+
+/// Provider which reads cwd and environment of window process from `/proc`
+#[derive(Debug, Clone, Default)]
+pub struct ProcfsProvider;
+
+impl ProcfsProvider {
+    /// Create provider
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl ContextProvider for ProcfsProvider {
+    fn name(&self) -> &str {
+        "procfs"
+    }
+
+    fn get_launching_data(
+        &self,
+        window: &niri_ipc::Window,
+    ) -> Result<LaunchingData> {
+        let pid = window_pid(window)?;
+        let launching_data = LaunchingData::default()
+            .set_envs(process::environ(pid)?.into_iter());
+
+        let cwd = process::cwd(pid)
+            .ok()
+            .and_then(|cwd| cwd.to_str().map(String::from));
+        Ok(launching_data.maybe_cwd(cwd))
+    }
+}
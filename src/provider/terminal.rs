@@ -0,0 +1,62 @@
+use super::{window_pid, ContextProvider};
+use crate::{multiplexer, process, ssh, Error, LaunchingData, Result};
+
+/// Provider for terminals without remote control
+///
+/// Finds the shell started by terminal process and takes cwd and environment
+/// of the foreground process of its tty. When single terminal process serves
+/// several windows (e.g. `foot --server`) the most recently started shell is
+/// used.
+#[derive(Debug, Clone, Default)]
+pub struct TerminalProvider;
+
+impl TerminalProvider {
+    /// Create provider
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Find the foreground process of terminal with `pid`
+    fn find_foreground_process(pid: i32) -> Result<i32> {
+        let descendants = process::descendants(pid)?;
+        // The shell is the outermost process with controlling terminal
+        let shell = descendants
+            .iter()
+            .filter(|(_, stat)| stat.tty_nr != 0)
+            .filter(|(_, stat)| {
+                !descendants.iter().any(|(parent, parent_stat)| {
+                    *parent == stat.ppid && parent_stat.tty_nr == stat.tty_nr
+                })
+            })
+            .max_by_key(|(_, stat)| stat.starttime)
+            .ok_or(Error::ProviderUnsupported(format!(
+                "No shell within terminal {pid}"
+            )))?;
+        let foreground = process::foreground(&descendants, shell.1.tty_nr)
+            .unwrap_or(shell.0);
+        Ok(foreground)
+    }
+}
+
+impl ContextProvider for TerminalProvider {
+    fn name(&self) -> &str {
+        "terminal"
+    }
+
+    fn get_launching_data(
+        &self,
+        window: &niri_ipc::Window,
+    ) -> Result<LaunchingData> {
+        let pid = Self::find_foreground_process(window_pid(window)?)?;
+        if let Some(data) = multiplexer::get_launching_data(pid) {
+            return Ok(data);
+        }
+        let cwd = process::cwd(pid)?;
+        let mut data = LaunchingData::default()
+            .maybe_cwd(cwd.to_str())
+            .set_envs(process::environ(pid)?.into_iter())
+            .complete_env();
+        data.ssh = ssh::of_process(pid);
+        Ok(data)
+    }
+}
@@ -0,0 +1,109 @@
+use super::{expand_env, window_pid, ContextProvider};
+use crate::{multiplexer, process, ssh, wezterm, Error, LaunchingData, Result};
+
+/// Provider which asks WezTerm for the active pane of window
+#[derive(Debug, Clone)]
+pub struct WeztermProvider {
+    command: String,
+    socket: String,
+}
+
+impl WeztermProvider {
+    /// Create provider running `command` with template of socket
+    ///
+    /// The template accepts environment variables in view `${ENV}` and `{pid}`
+    /// construction which will be replaced with pid of WezTerm GUI process.
+    pub fn new<C, S>(command: C, socket: S) -> Self
+    where
+        C: Into<String>,
+        S: Into<String>,
+    {
+        Self {
+            command: command.into(),
+            socket: socket.into(),
+        }
+    }
+
+    /// Get CLI runner for WezTerm GUI process with `pid`
+    pub(crate) fn cli(&self, pid: i32) -> wezterm::WeztermCli {
+        let socket =
+            expand_env(&self.socket).replace("{pid}", &pid.to_string());
+        wezterm::WeztermCli::new(&self.command, socket)
+    }
+
+    /// Find the active pane of niri `window`
+    ///
+    /// The niri window title is made of title of active pane, so panes are
+    /// matched by it first and the pane focused the last is used otherwise.
+    pub(crate) fn find_active_pane(
+        cli: &wezterm::WeztermCli,
+        window: &niri_ipc::Window,
+    ) -> Result<wezterm::Pane> {
+        let panes = cli.list()?;
+        let title = window.title.as_deref().unwrap_or_default();
+        let mut active = panes.iter().filter(|pane| pane.is_active);
+        let by_title: Vec<_> = active
+            .clone()
+            .filter(|pane| {
+                !pane.title.is_empty() && title.ends_with(&pane.title)
+            })
+            .collect();
+        if let [pane] = by_title[..] {
+            return Ok(pane.clone());
+        }
+        let focused = cli.focused_pane()?;
+        panes
+            .iter()
+            .find(|pane| Some(pane.pane_id) == focused)
+            .or_else(|| active.next())
+            .cloned()
+            .ok_or(Error::ProviderUnsupported("No active WezTerm pane".into()))
+    }
+
+    /// Get context of foreground process of pane
+    fn get_launching_data_from_process(
+        pid: i32,
+        pane: &wezterm::Pane,
+    ) -> Option<LaunchingData> {
+        let tty_nr = process::tty_nr(pane.tty_name.as_ref()?).ok()?;
+        let descendants = process::descendants(pid).ok()?;
+        let foreground = process::foreground(&descendants, tty_nr)?;
+        if let Some(data) = multiplexer::get_launching_data(foreground) {
+            return Some(data);
+        }
+        let env = process::environ(foreground).ok()?;
+        let cwd = process::cwd(foreground).ok().or(pane.local_cwd());
+        let mut data = LaunchingData::default()
+            .maybe_cwd(cwd.as_deref().and_then(|cwd| cwd.to_str()))
+            .set_envs(env.into_iter())
+            .complete_env();
+        // The cwd of remote shell is reported with OSC 7
+        let remote_cwd = pane
+            .cwd()
+            .filter(|_| pane.local_cwd().is_none())
+            .map(|(_, cwd)| cwd.to_string_lossy().into_owned());
+        data.ssh =
+            ssh::of_process(foreground).map(|ssh| ssh.with_cwd(remote_cwd));
+        Some(data)
+    }
+}
+
+impl ContextProvider for WeztermProvider {
+    fn name(&self) -> &str {
+        "wezterm"
+    }
+
+    fn get_launching_data(
+        &self,
+        window: &niri_ipc::Window,
+    ) -> Result<LaunchingData> {
+        let pid = window_pid(window)?;
+        let pane = Self::find_active_pane(&self.cli(pid), window)?;
+        if let Some(data) = Self::get_launching_data_from_process(pid, &pane) {
+            return Ok(data);
+        }
+        let cwd = pane.local_cwd();
+        Ok(LaunchingData::default()
+            .maybe_cwd(cwd.as_deref().and_then(|cwd| cwd.to_str())))
+    }
+}
@@ -0,0 +1,93 @@
+//!
+//! Quoting of values for shells and other consumers.
+//!
+
+/// Quote value for POSIX shell
+///
+/// Values consisting of safe characters only are left as is.
+pub fn sh(value: &str) -> String {
+    let safe = |c: char| c.is_ascii_alphanumeric() || "_-./:=@%+,".contains(c);
+    if !value.is_empty() && value.chars().all(safe) {
+        value.into()
+    } else {
+        format!("'{}'", value.replace('\'', r"'\''"))
+    }
+}
+
+/// Quote value for fish shell
+pub fn fish(value: &str) -> String {
+    format!("'{}'", value.replace('\\', r"\\").replace('\'', r"\'"))
+}
+
+/// Quote value for systemd environment files
+///
+/// Within double quotes systemd unescapes only the `\\`, `"`, `` ` `` and `$`,
+/// while newlines are kept as is.
+pub fn systemd(value: &str) -> String {
+    let mut res = String::with_capacity(value.len() + 2);
+    res.push('"');
+    for c in value.chars() {
+        if matches!(c, '\\' | '"' | '`' | '$') {
+            res.push('\\');
+        }
+        res.push(c);
+    }
+    res.push('"');
+    res
+}
+
+/// Whether `name` may be used as variable name within shells
+///
+/// The environment may hold other names too, e.g. `BASH_FUNC_foo%%` with
+/// exported bash function.
+pub fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sh_quotes() {
+        assert_eq!(sh("plain/path-1.0"), "plain/path-1.0");
+        assert_eq!(sh(""), "''");
+        assert_eq!(sh("a b"), "'a b'");
+        assert_eq!(sh("it's"), r"'it'\''s'");
+        assert_eq!(sh(r#""$HOME"\n"#), r#"'"$HOME"\n'"#);
+        assert_eq!(sh("a\nb"), "'a\nb'");
+    }
+
+    #[test]
+    fn fish_quotes() {
+        assert_eq!(fish("a b"), "'a b'");
+        assert_eq!(fish("it's"), r"'it\'s'");
+        assert_eq!(fish(r#""$HOME""#), r#"'"$HOME"'"#);
+        assert_eq!(fish(r"a\b\'"), r"'a\\b\\\''");
+        assert_eq!(fish("a\nb"), "'a\nb'");
+    }
+
+    #[test]
+    fn systemd_quotes() {
+        assert_eq!(systemd("a b"), r#""a b""#);
+        assert_eq!(systemd("it's"), r#""it's""#);
+        assert_eq!(systemd(r#"say "hi""#), r#""say \"hi\"""#);
+        assert_eq!(systemd("$HOME `id`"), r#""\$HOME \`id\`""#);
+        assert_eq!(systemd(r"a\b"), r#""a\\b""#);
+        assert_eq!(systemd("a\nb"), "\"a\nb\"");
+    }
+
+    #[test]
+    fn identifiers() {
+        assert!(is_identifier("PATH"));
+        assert!(is_identifier("_a1"));
+        assert!(!is_identifier(""));
+        assert!(!is_identifier("1A"));
+        assert!(!is_identifier("BASH_FUNC_foo%%"));
+        assert!(!is_identifier("A-B"));
+    }
+}
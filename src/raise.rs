@@ -0,0 +1,231 @@
+//!
+//! Run-or-raise support. When an existing niri window matches all the
+//! [Raise] matchers it is focused instead of launching new process.
+//!
+
+use crate::{
+    dry_run, mru, niri_request, niri_windows, Error, Launcher, LaunchingData,
+    Result,
+};
+use clap::{Args, ValueEnum};
+use niri_ipc::{socket::Socket, Action, Request, Response, Window};
+use std::{path::PathBuf, str::FromStr};
+
+/// Condition existing window should satisfy to be raised
+#[derive(Debug, Clone)]
+pub enum RaiseMatcher {
+    /// The app_id matches the whole regular expression
+    AppId(regex::Regex),
+    /// The title contains match of regular expression
+    Title(regex::Regex),
+    /// The cwd of window equals to the given one or to cwd of launching data
+    Cwd(Option<PathBuf>),
+}
+
+/// Which one of several matching windows to raise
+#[derive(ValueEnum, Debug, Clone, Copy, Default)]
+pub enum RaiseMode {
+    /// The most recently focused window
+    ///
+    /// Without running `daemon` the one with the largest id is used, which is
+    /// usually the most recently opened.
+    #[default]
+    Recent,
+    /// The most recently focused window on focused workspace
+    ///
+    /// New process is launched when there is no such window.
+    Workspace,
+    /// The window after focused one, so repeated runs cycle through matches
+    Cycle,
+}
+
+/// Run-or-raise options
+#[derive(Args, Debug, Clone, Default)]
+pub struct Raise {
+    /// Focus existing window matching all the conditions instead of launch
+    ///
+    /// Accepts `app_id:<regex>`, `title:<regex>`, `cwd` for the cwd of
+    /// launching data and `cwd:<path>`. May be repeated.
+    #[arg(long, value_name = "MATCHER")]
+    pub raise_if: Vec<RaiseMatcher>,
+
+    /// Which one of matching windows to focus
+    #[arg(long, value_enum, default_value_t = RaiseMode::Recent)]
+    pub raise_mode: RaiseMode,
+}
+
+impl FromStr for RaiseMatcher {
+    type Err = String;
+
+    fn from_str(value: &str) -> std::result::Result<Self, String> {
+        let regex =
+            |re: &str| regex::Regex::new(re).map_err(|err| err.to_string());
+        match value.split_once(':') {
+            Some(("app_id", re)) => {
+                Ok(Self::AppId(regex(&format!("^(?:{re})$"))?))
+            }
+            Some(("title", re)) => Ok(Self::Title(regex(re)?)),
+            Some(("cwd", path)) => Ok(Self::Cwd(Some(path.into()))),
+            None if value == "cwd" => Ok(Self::Cwd(None)),
+            _ => {
+                Err("expected app_id:<regex>, title:<regex>, cwd or cwd:<path>"
+                    .into())
+            }
+        }
+    }
+}
+
+impl Launcher {
+    /// Focus window matching [Raise] conditions if any
+    ///
+    /// Returns whether the window was found. The cwd of candidates is taken
+    /// with providers and compared with one of launching `data`.
+    pub(crate) fn raise(&self, data: &LaunchingData) -> Result<bool> {
+        if self.raise.raise_if.is_empty() {
+            return Ok(false);
+        }
+        let mut socket = self.connect_niri()?;
+        let windows = niri_windows(&mut socket)?;
+        let focused = windows.iter().find(|window| window.is_focused);
+        let focused = focused.map(|window| (window.id, window.workspace_id));
+
+        let mut matches: Vec<_> = windows
+            .iter()
+            .filter(|window| self.raise_matches(window, data))
+            .collect();
+        mru::sort_by_opening(&mut matches, |window| window.id);
+        if !matches!(self.raise.raise_mode, RaiseMode::Cycle) {
+            // Cycling requires stable order
+            mru::History::load().sort(&mut matches, |window| window.id);
+        }
+
+        let window = match self.raise.raise_mode {
+            RaiseMode::Recent => matches.first(),
+            RaiseMode::Workspace => {
+                let workspace = match focused {
+                    Some((_, workspace)) => workspace,
+                    None => focused_workspace(&mut socket)?,
+                };
+                matches
+                    .iter()
+                    .find(|window| window.workspace_id == workspace)
+            }
+            RaiseMode::Cycle => focused
+                .and_then(|(id, _)| {
+                    matches.iter().position(|window| window.id == id)
+                })
+                .and_then(|pos| matches.get(pos + 1))
+                .or(matches.first()),
+        };
+        let Some(window) = window else {
+            return Ok(false);
+        };
+
+        if let Some(format) = self.dry_run {
+            let mut proc = std::process::Command::new("niri");
+            proc.args(["msg", "action", "focus-window", "--id"])
+                .arg(window.id.to_string());
+            dry_run::print(&proc, format);
+            return Ok(true);
+        }
+        let id = window.id;
+        match niri_request(
+            &mut socket,
+            Request::Action(Action::FocusWindow { id }),
+        )? {
+            Response::Handled => Ok(true),
+            _ => {
+                Err(Error::NiriProtocol("Unexpected response to Action".into()))
+            }
+        }
+    }
+
+    fn raise_matches(&self, window: &Window, data: &LaunchingData) -> bool {
+        let app_id = window.app_id.as_deref().unwrap_or_default();
+        let title = window.title.as_deref().unwrap_or_default();
+        // Asking providers is expensive, so cwd is checked last and once
+        let mut cwd = None;
+        let mut window_cwd = || {
+            cwd.get_or_insert_with(|| {
+                self.providers
+                    .get_launching_data(window)
+                    .ok()
+                    .and_then(|data| data.cwd)
+                    .map(PathBuf::from)
+            })
+            .clone()
+        };
+        let (mut rest, cwds): (Vec<_>, Vec<_>) = self
+            .raise
+            .raise_if
+            .iter()
+            .partition(|matcher| !matches!(matcher, RaiseMatcher::Cwd(_)));
+        rest.extend(cwds);
+        rest.into_iter().all(|matcher| match matcher {
+            RaiseMatcher::AppId(re) => re.is_match(app_id),
+            RaiseMatcher::Title(re) => re.is_match(title),
+            RaiseMatcher::Cwd(path) => {
+                let expected =
+                    path.clone().or(data.cwd.as_ref().map(PathBuf::from));
+                expected.is_some() && window_cwd() == expected
+            }
+        })
+    }
+}
+
+fn focused_workspace(socket: &mut Socket) -> Result<Option<u64>> {
+    match niri_request(socket, Request::Workspaces)? {
+        Response::Workspaces(workspaces) => Ok(workspaces
+            .into_iter()
+            .find(|workspace| workspace.is_focused)
+            .map(|workspace| workspace.id)),
+        _ => Err(Error::NiriProtocol(
+            "Unexpected response to Workspaces".into(),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(value: &str) -> RaiseMatcher {
+        value.parse().unwrap()
+    }
+
+    #[test]
+    fn app_id_is_anchored() {
+        let RaiseMatcher::AppId(re) = parse("app_id:kitty") else {
+            panic!("expected app_id matcher");
+        };
+        assert!(re.is_match("kitty"));
+        assert!(!re.is_match("kitty-dev"));
+        assert!(!re.is_match("my-kitty"));
+    }
+
+    #[test]
+    fn title_is_not_anchored() {
+        let RaiseMatcher::Title(re) = parse("title:vim") else {
+            panic!("expected title matcher");
+        };
+        assert!(re.is_match("vim"));
+        assert!(re.is_match("file.rs - nvim"));
+        assert!(!re.is_match("emacs"));
+    }
+
+    #[test]
+    fn cwd_forms() {
+        assert!(matches!(parse("cwd"), RaiseMatcher::Cwd(None)));
+        let RaiseMatcher::Cwd(Some(path)) = parse("cwd:/home/user") else {
+            panic!("expected cwd matcher with path");
+        };
+        assert_eq!(path, PathBuf::from("/home/user"));
+    }
+
+    #[test]
+    fn rejects_invalid() {
+        for value in ["", "app:kitty", "cwd=/tmp", "title", "app_id:("] {
+            assert!(value.parse::<RaiseMatcher>().is_err(), "{value}");
+        }
+    }
+}
@@ -0,0 +1,280 @@
+//!
+//! Selection of base window with `--window` argument. The selectors are
+//! resolved against niri windows and workspaces. When several windows match
+//! the most recently focused one wins.
+//!
+
+use crate::{
+    mru, niri_focused_window, niri_request, niri_windows, process, Error,
+    ProviderRegistry, Result,
+};
+use niri_ipc::{socket::Socket, Action, Request, Response, Window};
+use std::str::FromStr;
+
+/// The way to choose base window
+#[derive(Debug, Clone)]
+pub enum WindowSelector {
+    /// Niri window id
+    Id(u64),
+    /// The N-th window of focus history, `mru:0` is the focused one
+    ///
+    /// Requires running `daemon`.
+    Mru(usize),
+    /// The window which app_id matches the whole regular expression
+    AppId(regex::Regex),
+    /// The window which title contains match of regular expression
+    Title(regex::Regex),
+    /// The window of process or of its nearest ancestor
+    Pid(i32),
+    /// The window with usable context on named workspace
+    Workspace(String),
+    /// The window of neighbor column on focused workspace
+    ColumnNeighbor(Side),
+    /// The window picked interactively with mouse
+    Pick,
+}
+
+/// The side of neighbor column
+#[derive(Debug, Clone, Copy)]
+pub enum Side {
+    /// The column to the left
+    Left,
+    /// The column to the right
+    Right,
+}
+
+impl FromStr for WindowSelector {
+    type Err = String;
+
+    fn from_str(value: &str) -> std::result::Result<Self, String> {
+        let invalid = |n: &str| format!("{n} is not a number");
+        let regex =
+            |re: &str| regex::Regex::new(re).map_err(|err| err.to_string());
+        match value.split_once(':') {
+            Some(("mru", n)) => {
+                n.parse().map(Self::Mru).map_err(|_| invalid(n))
+            }
+            Some(("app", re)) => regex(&format!("^(?:{re})$")).map(Self::AppId),
+            Some(("title", re)) => {
+                // The slashes around regex are optional
+                let re = re
+                    .strip_prefix('/')
+                    .and_then(|re| re.strip_suffix('/'))
+                    .unwrap_or(re);
+                regex(re).map(Self::Title)
+            }
+            Some(("pid", n)) => {
+                n.parse().map(Self::Pid).map_err(|_| invalid(n))
+            }
+            Some(("workspace", name)) => Ok(Self::Workspace(name.into())),
+            Some(("column-neighbor", "left")) => {
+                Ok(Self::ColumnNeighbor(Side::Left))
+            }
+            Some(("column-neighbor", "right")) => {
+                Ok(Self::ColumnNeighbor(Side::Right))
+            }
+            None if value == "pick" => Ok(Self::Pick),
+            None => value.parse().map(Self::Id).map_err(|_| invalid(value)),
+            _ => Err("expected window id, mru:<N>, app:<regex>, \
+                      title:/<regex>/, pid:<N>, workspace:<name>, \
+                      column-neighbor:left|right or pick"
+                .into()),
+        }
+    }
+}
+
+impl WindowSelector {
+    /// Find the window with `socket`
+    ///
+    /// The `providers` decide which windows have usable context.
+    pub(crate) fn select(
+        &self,
+        socket: &mut Socket,
+        providers: &ProviderRegistry,
+    ) -> Result<Option<Window>> {
+        match self {
+            Self::Pick => {
+                return match niri_request(socket, Request::PickWindow)? {
+                    Response::PickedWindow(window) => Ok(window),
+                    _ => Err(Error::NiriProtocol(
+                        "Unexpected response to PickWindow".into(),
+                    )),
+                }
+            }
+            Self::ColumnNeighbor(side) => {
+                return column_neighbor(socket, *side);
+            }
+            _ => (),
+        }
+
+        let mut windows = niri_windows(socket)?;
+        mru::sort_by_opening(&mut windows, |window| window.id);
+        let history = mru::History::load();
+        history.sort(&mut windows, |window| window.id);
+        let has = |value: &Option<String>, re: &regex::Regex| {
+            re.is_match(value.as_deref().unwrap_or_default())
+        };
+
+        let window = match self {
+            Self::Id(id) => windows.into_iter().find(|window| window.id == *id),
+            Self::Mru(n) => windows
+                .into_iter()
+                .filter(|window| history.ids().contains(&window.id))
+                .nth(*n),
+            Self::AppId(re) => {
+                windows.into_iter().find(|window| has(&window.app_id, re))
+            }
+            Self::Title(re) => {
+                windows.into_iter().find(|window| has(&window.title, re))
+            }
+            Self::Pid(pid) => process::ancestors(*pid).find_map(|pid| {
+                windows
+                    .iter()
+                    .find(|window| window.pid == Some(pid))
+                    .cloned()
+            }),
+            Self::Workspace(name) => {
+                let workspace = match niri_request(socket, Request::Workspaces)?
+                {
+                    Response::Workspaces(workspaces) => workspaces
+                        .into_iter()
+                        .find(|ws| ws.name.as_ref() == Some(name))
+                        .ok_or(Error::NiriProtocol(format!(
+                            "No workspace named {name}"
+                        )))?,
+                    _ => {
+                        return Err(Error::NiriProtocol(
+                            "Unexpected response to Workspaces".into(),
+                        ))
+                    }
+                };
+                windows.into_iter().find(|window| {
+                    window.workspace_id == Some(workspace.id)
+                        && window.app_id.as_deref().is_some_and(|app_id| {
+                            providers.find(app_id).is_some()
+                        })
+                })
+            }
+            Self::ColumnNeighbor(_) | Self::Pick => unreachable!(),
+        };
+        Ok(window)
+    }
+}
+
+/// Find the window of neighbor column
+///
+/// Niri does not report layout of windows, so the neighbor column is
+/// focused for a moment and the focus is returned back after. This may be
+/// seen as a brief focus flash. The `daemon` is told to ignore these focus
+/// changes, so focus history stays intact.
+fn column_neighbor(socket: &mut Socket, side: Side) -> Result<Option<Window>> {
+    let Some(origin) = niri_focused_window(socket)? else {
+        return Ok(None);
+    };
+    let action = match side {
+        Side::Left => Action::FocusColumnLeft {},
+        Side::Right => Action::FocusColumnRight {},
+    };
+    mru::begin_probe(origin.id)?;
+    let neighbor = niri_request(socket, Request::Action(action))
+        .and_then(|_| niri_focused_window(socket));
+    // Focus does not move from the outermost column
+    let neighbor = match neighbor {
+        Ok(Some(neighbor)) if neighbor.id != origin.id => neighbor,
+        neighbor => {
+            mru::cancel_probe();
+            return neighbor.map(|_| None);
+        }
+    };
+    let refocus = niri_request(
+        socket,
+        Request::Action(Action::FocusWindow { id: origin.id }),
+    );
+    if refocus.is_err() {
+        mru::cancel_probe();
+    }
+    refocus.map(|_| Some(neighbor))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(value: &str) -> WindowSelector {
+        value.parse().unwrap()
+    }
+
+    #[test]
+    fn parse_ids() {
+        assert!(matches!(parse("42"), WindowSelector::Id(42)));
+        assert!(matches!(parse("mru:0"), WindowSelector::Mru(0)));
+        assert!(matches!(parse("mru:3"), WindowSelector::Mru(3)));
+        assert!(matches!(parse("pid:1234"), WindowSelector::Pid(1234)));
+    }
+
+    #[test]
+    fn parse_app_id_is_anchored() {
+        let WindowSelector::AppId(re) = parse("app:kitty|foot") else {
+            panic!("expected app_id selector");
+        };
+        assert!(re.is_match("kitty"));
+        assert!(re.is_match("foot"));
+        assert!(!re.is_match("kitty-dev"));
+        assert!(!re.is_match("footclient"));
+    }
+
+    #[test]
+    fn parse_title() {
+        for value in ["title:/vim$/", "title:vim$"] {
+            let WindowSelector::Title(re) = parse(value) else {
+                panic!("expected title selector for {value}");
+            };
+            assert_eq!(re.as_str(), "vim$");
+            assert!(re.is_match("file.rs - nvim"));
+        }
+        // Only the pair of slashes is stripped
+        let WindowSelector::Title(re) = parse("title:/a/b") else {
+            panic!("expected title selector");
+        };
+        assert_eq!(re.as_str(), "/a/b");
+    }
+
+    #[test]
+    fn parse_named() {
+        let WindowSelector::Workspace(name) = parse("workspace:dev:1") else {
+            panic!("expected workspace selector");
+        };
+        assert_eq!(name, "dev:1");
+        assert!(matches!(
+            parse("column-neighbor:left"),
+            WindowSelector::ColumnNeighbor(Side::Left)
+        ));
+        assert!(matches!(
+            parse("column-neighbor:right"),
+            WindowSelector::ColumnNeighbor(Side::Right)
+        ));
+        assert!(matches!(parse("pick"), WindowSelector::Pick));
+    }
+
+    #[test]
+    fn parse_rejects_invalid() {
+        for value in [
+            "",
+            "focused",
+            "-1",
+            "mru:x",
+            "mru:-1",
+            "pid:",
+            "app:(",
+            "title:/(/",
+            "column-neighbor:up",
+            "pick:1",
+            "window:1",
+        ] {
+            assert!(
+                value.parse::<WindowSelector>().is_err(),
+                "{value} is accepted"
+            );
+        }
+    }
+}
@@ -0,0 +1,234 @@
+//!
+//! Recognition of ssh connections. The command line of `ssh` or of kitty
+//! `ssh` kitten is parsed into [SshTarget] which rebuilds the connection to
+//! the same destination for new terminal.
+//!
+
+use crate::{process, quote};
+use std::path::Path;
+
+/// Options of ssh which take value
+const WITH_VALUE: &str = "BbcDEeFIiJLlmOoPpQRSWw";
+
+/// Options of ssh which would break or duplicate new connection
+///
+/// These are port forwardings, control commands and ones disabling shell.
+const DROPPED: &str = "DLRWOfGNnT";
+
+/// The ssh connection of terminal
+#[derive(Debug, Clone)]
+pub struct SshTarget {
+    /// The program with subcommand, e.g. `ssh` or `kitten ssh`
+    pub program: Vec<String>,
+    /// The options of connection
+    pub options: Vec<String>,
+    /// The destination host, e.g. `user@host`
+    pub destination: String,
+    /// The cwd within remote host if known
+    pub cwd: Option<String>,
+}
+
+impl SshTarget {
+    /// Parse command line of `ssh` or kitty `ssh` kitten
+    ///
+    /// The remote command is dropped, so new connection runs shell.
+    pub fn parse(cmdline: &[String]) -> Option<Self> {
+        let name = |arg: &String| {
+            Path::new(arg).file_name()?.to_str().map(String::from)
+        };
+        let skip = match (name(cmdline.first()?)?.as_str(), &cmdline[1..]) {
+            ("ssh", _) => 1,
+            ("kitten", [ssh, ..]) if ssh == "ssh" => 2,
+            ("kitty", [kitten, ssh, ..])
+                if kitten == "+kitten" && ssh == "ssh" =>
+            {
+                3
+            }
+            _ => return None,
+        };
+        let program = cmdline[..skip].to_vec();
+        let mut options = Vec::new();
+        let mut args = cmdline[skip..].iter();
+        let destination = loop {
+            let arg = args.next()?;
+            if arg == "--" {
+                break args.next()?.clone();
+            }
+            if arg == "--kitten" {
+                options.extend([arg.clone(), args.next()?.clone()]);
+                continue;
+            }
+            if arg.starts_with("--") {
+                options.push(arg.clone());
+                continue;
+            }
+            let Some(flags) = arg.strip_prefix('-').filter(|f| !f.is_empty())
+            else {
+                break arg.clone();
+            };
+            // Flags may be combined, e.g. `-At` or `-p22`
+            let mut kept = String::new();
+            let mut with_value = None;
+            for (pos, flag) in flags.char_indices() {
+                if WITH_VALUE.contains(flag) {
+                    let value = match &flags[pos + flag.len_utf8()..] {
+                        "" => args.next()?.clone(),
+                        value => value.into(),
+                    };
+                    with_value = Some((flag, value));
+                    break;
+                }
+                if !DROPPED.contains(flag) {
+                    kept.push(flag);
+                }
+            }
+            if !kept.is_empty() {
+                options.push(format!("-{kept}"));
+            }
+            match with_value {
+                Some((flag, value)) if !DROPPED.contains(flag) => {
+                    options.extend([format!("-{flag}"), value]);
+                }
+                _ => (),
+            }
+        };
+        Some(Self {
+            program,
+            options,
+            destination,
+            cwd: None,
+        })
+    }
+
+    /// Set the cwd within remote host
+    pub fn with_cwd(mut self, cwd: Option<String>) -> Self {
+        self.cwd = cwd;
+        self
+    }
+
+    /// Whether the connection is made with kitty `ssh` kitten
+    pub fn is_kitten(&self) -> bool {
+        self.program.len() > 1
+    }
+
+    /// Get command line connecting to the same destination
+    ///
+    /// Changes to remote cwd if it is known.
+    pub fn command(&self) -> Vec<String> {
+        let mut args = self.program.clone();
+        args.extend(self.options.iter().cloned());
+        match self.cwd.as_ref() {
+            Some(cwd) if self.is_kitten() => {
+                args.extend(["--kitten".into(), format!("cwd={cwd}")]);
+                args.push(self.destination.clone());
+            }
+            Some(cwd) => {
+                args.extend(["-t".into(), self.destination.clone()]);
+                args.push(format!(
+                    "cd {} && exec \"$SHELL\" -l",
+                    quote::sh(cwd)
+                ));
+            }
+            None => args.push(self.destination.clone()),
+        }
+        args
+    }
+}
+
+/// Get ssh connection made by process `pid`
+pub(crate) fn of_process(pid: i32) -> Option<SshTarget> {
+    SshTarget::parse(&process::cmdline(pid).ok()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(cmdline: &str) -> Option<SshTarget> {
+        let args: Vec<_> = cmdline.split(' ').map(String::from).collect();
+        SshTarget::parse(&args)
+    }
+
+    fn command(cmdline: &str, cwd: Option<&str>) -> Vec<String> {
+        let target = parse(cmdline).expect("ssh command line");
+        target.with_cwd(cwd.map(String::from)).command()
+    }
+
+    #[test]
+    fn parse_plain_ssh() {
+        let target = parse("/usr/bin/ssh -A user@host").unwrap();
+        assert_eq!(target.program, ["/usr/bin/ssh"]);
+        assert_eq!(target.options, ["-A"]);
+        assert_eq!(target.destination, "user@host");
+        assert!(!target.is_kitten());
+        assert!(parse("sshd -D").is_none());
+        assert!(parse("ssh -p 22").is_none());
+    }
+
+    #[test]
+    fn parse_combined_flags() {
+        let target = parse("ssh -Atp 2222 host").unwrap();
+        assert_eq!(target.options, ["-At", "-p", "2222"]);
+        let target = parse("ssh -p22 -i key host").unwrap();
+        assert_eq!(target.options, ["-p", "22", "-i", "key"]);
+        let target = parse("ssh -Ap22 host").unwrap();
+        assert_eq!(target.options, ["-A", "-p", "22"]);
+    }
+
+    #[test]
+    fn parse_drops_remote_command() {
+        let target = parse("ssh host tail -f log").unwrap();
+        assert_eq!(target.destination, "host");
+        assert!(target.options.is_empty());
+        let target = parse("ssh -A -- -host ls").unwrap();
+        assert_eq!(target.options, ["-A"]);
+        assert_eq!(target.destination, "-host");
+    }
+
+    #[test]
+    fn parse_drops_forwardings() {
+        let target = parse("ssh -L 8080:localhost:80 -fN -A host").unwrap();
+        assert_eq!(target.options, ["-A"]);
+        let target = parse("ssh -NL8080:localhost:80 -R 9000:x:9000 host");
+        assert!(target.unwrap().options.is_empty());
+    }
+
+    #[test]
+    fn parse_kitten() {
+        let target = parse("kitten ssh --kitten cwd=/x -p 22 host").unwrap();
+        assert_eq!(target.program, ["kitten", "ssh"]);
+        assert_eq!(target.options, ["--kitten", "cwd=/x", "-p", "22"]);
+        assert!(target.is_kitten());
+        let target = parse("kitty +kitten ssh host").unwrap();
+        assert_eq!(target.program, ["kitty", "+kitten", "ssh"]);
+        assert!(target.is_kitten());
+        assert!(parse("kitten icat file").is_none());
+    }
+
+    #[test]
+    fn command_without_cwd() {
+        assert_eq!(
+            command("ssh -p22 host ls", None),
+            ["ssh", "-p", "22", "host"]
+        );
+        assert_eq!(command("kitten ssh host", None), ["kitten", "ssh", "host"]);
+    }
+
+    #[test]
+    fn command_with_cwd() {
+        assert_eq!(
+            command("ssh -A host", Some("/srv/my app")),
+            [
+                "ssh",
+                "-A",
+                "-t",
+                "host",
+                r#"cd '/srv/my app' && exec "$SHELL" -l"#
+            ]
+        );
+        assert_eq!(
+            command("kitten ssh host", Some("/srv")),
+            ["kitten", "ssh", "--kitten", "cwd=/srv", "host"]
+        );
+    }
+}
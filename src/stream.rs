@@ -0,0 +1,39 @@
+//!
+//! Connection to unix or tcp socket.
+//!
+
+use std::io::{self, Read, Write};
+use std::{net::TcpStream, os::unix::net::UnixStream};
+
+/// Stream of either unix or tcp socket
+pub enum Stream {
+    /// Unix socket
+    Unix(UnixStream),
+    /// Tcp socket
+    Tcp(TcpStream),
+}
+
+impl Read for Stream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Self::Unix(stream) => stream.read(buf),
+            Self::Tcp(stream) => stream.read(buf),
+        }
+    }
+}
+
+impl Write for Stream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Self::Unix(stream) => stream.write(buf),
+            Self::Tcp(stream) => stream.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Self::Unix(stream) => stream.flush(),
+            Self::Tcp(stream) => stream.flush(),
+        }
+    }
+}
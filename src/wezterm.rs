@@ -0,0 +1,211 @@
+//!
+//! WezTerm multiplexer command line interface. The [WeztermCli] runs
+//! `wezterm cli` against the socket of particular GUI instance.
+//!
+
+use crate::{Error, Result};
+use serde::{de::DeserializeOwned, Deserialize};
+use std::{fs, path::PathBuf, process::Command};
+
+/// The pane as reported by `wezterm cli list`
+#[derive(Debug, Deserialize, Clone)]
+pub struct Pane {
+    pub pane_id: u64,
+    #[serde(default)]
+    pub title: String,
+    /// The `file://host/path` url set with OSC 7 or by local process
+    #[serde(default)]
+    pub cwd: String,
+    #[serde(default)]
+    pub is_active: bool,
+    #[serde(default)]
+    pub tty_name: Option<PathBuf>,
+}
+
+/// The client as reported by `wezterm cli list-clients`
+#[derive(Debug, Deserialize, Clone)]
+pub struct Client {
+    #[serde(default)]
+    pub focused_pane_id: Option<u64>,
+}
+
+/// Runner of `wezterm cli` commands
+#[derive(Debug, Clone)]
+pub struct WeztermCli {
+    command: String,
+    socket: String,
+}
+
+impl WeztermCli {
+    /// Create runner of `command` binary talking to `socket`
+    pub fn new<C, S>(command: C, socket: S) -> Self
+    where
+        C: Into<String>,
+        S: Into<String>,
+    {
+        Self {
+            command: command.into(),
+            socket: socket.into(),
+        }
+    }
+
+    /// Get `wezterm cli` command with subcommand `args`
+    ///
+    /// The command never starts new multiplexer server.
+    pub fn command<I, S>(&self, args: I) -> Command
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<std::ffi::OsStr>,
+    {
+        let mut proc = Command::new(&self.command);
+        proc.env("WEZTERM_UNIX_SOCKET", &self.socket)
+            .args(["cli", "--no-auto-start"])
+            .args(args);
+        proc
+    }
+
+    /// List all panes of instance
+    pub fn list(&self) -> Result<Vec<Pane>> {
+        self.run(["list", "--format", "json"])
+    }
+
+    /// Get the pane which was focused the last
+    pub fn focused_pane(&self) -> Result<Option<u64>> {
+        let clients: Vec<Client> =
+            self.run(["list-clients", "--format", "json"])?;
+        Ok(clients
+            .into_iter()
+            .find_map(|client| client.focused_pane_id))
+    }
+
+    fn run<T, const N: usize>(&self, args: [&str; N]) -> Result<T>
+    where
+        T: DeserializeOwned,
+    {
+        let output = output(self.command(args))?;
+        serde_json::from_slice(&output)
+            .map_err(|err| Error::WeztermFailed(err.to_string()))
+    }
+}
+
+impl Pane {
+    /// Split cwd url into host and path
+    ///
+    /// The host is empty when it was not reported.
+    pub fn cwd(&self) -> Option<(String, PathBuf)> {
+        let rest = self.cwd.strip_prefix("file://")?;
+        let (host, path) = rest.split_at(rest.find('/')?);
+        Some((host.into(), percent_decode(path).into()))
+    }
+
+    /// Get local cwd of pane
+    ///
+    /// The cwd of panes on other hosts is meaningless locally.
+    pub fn local_cwd(&self) -> Option<PathBuf> {
+        let (host, path) = self.cwd()?;
+        (host.is_empty() || host == "localhost" || Some(host) == hostname())
+            .then_some(path)
+    }
+}
+
+/// Run `wezterm cli` command and get its output
+pub fn output(mut proc: Command) -> Result<Vec<u8>> {
+    let output = proc.output().map_err(Error::ExecFailed)?;
+    if !output.status.success() {
+        return Err(Error::WeztermFailed(
+            String::from_utf8_lossy(&output.stderr).trim().into(),
+        ));
+    }
+    Ok(output.stdout)
+}
+
+/// Get name of local host
+pub fn hostname() -> Option<String> {
+    fs::read_to_string("/proc/sys/kernel/hostname")
+        .ok()
+        .map(|name| name.trim().into())
+}
+
+/// Decode `%XX` sequences of url path
+fn percent_decode(value: &str) -> String {
+    let mut bytes = Vec::with_capacity(value.len());
+    let mut rest = value.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        let hex = tail
+            .get(..2)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (byte, hex) {
+            (b'%', Some(decoded)) => {
+                bytes.push(decoded);
+                rest = &tail[2..];
+            }
+            _ => {
+                bytes.push(byte);
+                rest = tail;
+            }
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pane(cwd: &str) -> Pane {
+        Pane {
+            pane_id: 0,
+            title: String::new(),
+            cwd: cwd.into(),
+            is_active: true,
+            tty_name: None,
+        }
+    }
+
+    #[test]
+    fn decode_percent() {
+        assert_eq!(percent_decode("/plain/path"), "/plain/path");
+        assert_eq!(percent_decode("/my%20dir"), "/my dir");
+        assert_eq!(percent_decode("/%D0%BF%D1%80%D0%B8"), "/при");
+        assert_eq!(percent_decode("/100%25"), "/100%");
+        assert_eq!(percent_decode("/a%4"), "/a%4");
+        assert_eq!(percent_decode("/a%"), "/a%");
+        assert_eq!(percent_decode("/a%zz"), "/a%zz");
+    }
+
+    #[test]
+    fn cwd_with_empty_host() {
+        let pane = pane("file:///home/user/my%20dir");
+        assert_eq!(
+            pane.cwd(),
+            Some((String::new(), PathBuf::from("/home/user/my dir")))
+        );
+        assert_eq!(pane.local_cwd(), Some(PathBuf::from("/home/user/my dir")));
+    }
+
+    #[test]
+    fn cwd_with_hosts() {
+        let local = pane("file://localhost/srv");
+        assert_eq!(local.local_cwd(), Some(PathBuf::from("/srv")));
+
+        let remote = pane("file://remote.invalid/srv/app");
+        assert_eq!(
+            remote.cwd(),
+            Some(("remote.invalid".into(), PathBuf::from("/srv/app")))
+        );
+        assert_eq!(remote.local_cwd(), None);
+
+        if let Some(host) = hostname() {
+            let own = pane(&format!("file://{host}/srv"));
+            assert_eq!(own.local_cwd(), Some(PathBuf::from("/srv")));
+        }
+    }
+
+    #[test]
+    fn cwd_without_url() {
+        assert_eq!(pane("").cwd(), None);
+        assert_eq!(pane("/home/user").cwd(), None);
+        assert_eq!(pane("file://host").cwd(), None);
+    }
+}